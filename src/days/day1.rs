@@ -1,65 +1,87 @@
-use crate::helpers::FirstAndLast;
+use crate::helpers::token_scanner::TokenScanner;
 
 use super::day::Day;
-use anyhow::Result;
+use anyhow::{anyhow, Context, Result};
+
+const DIGITS: [(&str, usize); 10] = [
+    ("0", 0),
+    ("1", 1),
+    ("2", 2),
+    ("3", 3),
+    ("4", 4),
+    ("5", 5),
+    ("6", 6),
+    ("7", 7),
+    ("8", 8),
+    ("9", 9),
+];
+const WORDS: [(&str, usize); 10] = [
+    ("zero", 0),
+    ("one", 1),
+    ("two", 2),
+    ("three", 3),
+    ("four", 4),
+    ("five", 5),
+    ("six", 6),
+    ("seven", 7),
+    ("eight", 8),
+    ("nine", 9),
+];
+
+/// The first and last value on the line, read as a two digit number.
+fn calibration_value(line: &str, scanner: &TokenScanner<usize>) -> Option<usize> {
+    Some(scanner.first(line)?.value * 10 + scanner.last(line)?.value)
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Calibration {
+    /// Value from the digits alone, or why the line has none when it only spells them out
+    digits: Result<usize, String>,
+    /// Value from digits and spelled out words
+    spelled: usize,
+}
+
+/// Sum of the values from digits alone, failing on the first line without any digit.
+fn digit_sum(calibrations: &[Calibration]) -> Result<usize> {
+    calibrations
+        .iter()
+        .map(|c| c.digits.clone().map_err(|e| anyhow!(e)))
+        .sum()
+}
 
 pub struct Day1;
 impl Day for Day1 {
-    type Parsed = String;
+    type Parsed = Vec<Calibration>;
     type Output = usize;
 
     fn parse(input: String) -> Result<Self::Parsed> {
-        Ok(input)
-    }
-    fn first(input: Self::Parsed) -> Self::Output {
+        let digits = TokenScanner::new(DIGITS);
+        let spelled = TokenScanner::new(DIGITS.into_iter().chain(WORDS));
         input
             .lines()
-            .map(|line| {
-                let mut it = line.chars().filter(char::is_ascii_digit);
-                let first = it.next().unwrap().to_digit(10).unwrap();
-                let last = if let Some(last) = it.next_back() {
-                    last.to_digit(10).unwrap()
-                } else {
-                    first
-                };
-                (first * 10 + last) as usize
+            .enumerate()
+            .map(|(i, line)| {
+                let missing = || format!("No calibration value on line {}: {line:?}", i + 1);
+                Ok(Calibration {
+                    digits: calibration_value(line, &digits).ok_or_else(missing),
+                    spelled: calibration_value(line, &spelled).with_context(missing)?,
+                })
             })
-            .sum()
+            .collect()
     }
-    fn second(input: Self::Parsed) -> Self::Output {
-        let numbers = [
-            "zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
-        ];
-        input
-            .lines()
-            .map(|line| {
-                let mut fl = FirstAndLast::new();
-                for pos in 0..(line.len()) {
-                    let slice = &line[pos..];
-                    let char = slice.chars().next().unwrap();
-                        match char {
-                            '0'..='9' => {
-                                fl.push(char.to_digit(10).unwrap() as usize);
-                            }
-                            _ => {
-                                for (i, number) in numbers.iter().enumerate() {
-                                    if slice.starts_with(number) {
-                                        fl.push(i);
-                                    }
-                                }
-                            }
-                        }
-                }
-                let (first, last) = fl.get().unwrap();
-                first * 10 + last
-            })
-            .sum()
+    fn first(calibrations: Self::Parsed) -> Self::Output {
+        digit_sum(&calibrations).unwrap_or_else(|e| panic!("{e}"))
+    }
+    fn second(calibrations: Self::Parsed) -> Self::Output {
+        calibrations.iter().map(|c| c.spelled).sum()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::helpers::{test_rng::XorShift, FirstAndLast};
+    use std::time::Instant;
     const INPUT: &str = "1abc2
 pqr3stu8vwx
 a1b2c3d4e5f
@@ -85,4 +107,87 @@ zoneight234
     fn part2() {
         assert_eq!(Day1::second(parsed2()), 281);
     }
+    #[test]
+    fn missing_value() {
+        let error = Day1::parse("1a\nabc\n".to_string()).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "No calibration value on line 2: \"abc\""
+        );
+        // Spelled out digits are enough for part 2, but part 1 can't read that line
+        let parsed = Day1::parse("1a\nnine\n".to_string()).unwrap();
+        assert_eq!(Day1::second(parsed.clone()), 11 + 99);
+        assert_eq!(
+            digit_sum(&parsed).unwrap_err().to_string(),
+            "No calibration value on line 2: \"nine\""
+        );
+    }
+    #[test]
+    #[should_panic(expected = "No calibration value on line 2")]
+    fn missing_digit() {
+        Day1::first(Day1::parse("1a\nnine\n".to_string()).unwrap());
+    }
+    #[test]
+    fn other_words() {
+        let scanner = TokenScanner::new(DIGITS.into_iter().chain([("eins", 1), ("neun", 9)]));
+        assert_eq!(calibration_value("neuneins", &scanner), Some(91));
+        assert_eq!(calibration_value("xeinsx", &scanner), Some(11));
+        assert_eq!(calibration_value("xeinx", &scanner), None);
+    }
+
+    /// The scanner this replaced, trying every word at every offset.
+    fn starts_with_sum(input: &str) -> usize {
+        input
+            .lines()
+            .map(|line| {
+                let mut fl = FirstAndLast::new();
+                for pos in 0..line.len() {
+                    let slice = &line[pos..];
+                    let char = slice.chars().next().unwrap();
+                    if let Some(digit) = char.to_digit(10) {
+                        fl.push(digit as usize);
+                    }
+                    for (word, value) in WORDS {
+                        if slice.starts_with(word) {
+                            fl.push(value);
+                        }
+                    }
+                }
+                let (first, last) = fl.get().unwrap();
+                first * 10 + last
+            })
+            .sum()
+    }
+    /// Run with `cargo test --release day1::tests::benchmark -- --ignored --nocapture`.
+    #[test]
+    #[ignore = "timing comparison, best run in release mode"]
+    #[allow(clippy::cast_possible_truncation)]
+    fn benchmark() {
+        let mut rng = XorShift::new(1);
+        let letters = b"abcdefghijklmnopqrstuvwxyz0123456789eeinnnoorttvx";
+        let input = (0..100_000)
+            .map(|_| {
+                let len = rng.range(8..60) as usize;
+                let mut line: String = (0..len)
+                    .map(|_| letters[rng.range(0..letters.len() as u64) as usize] as char)
+                    .collect();
+                line.push('7');
+                line
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let start = Instant::now();
+        let expected = starts_with_sum(&input);
+        let old = start.elapsed();
+        let start = Instant::now();
+        let scanner = TokenScanner::new(DIGITS.into_iter().chain(WORDS));
+        let found: usize = input
+            .lines()
+            .map(|line| calibration_value(line, &scanner).unwrap())
+            .sum();
+        let new = start.elapsed();
+        println!("starts_with: {old:?}, automaton: {new:?}");
+        assert_eq!(found, expected);
+    }
 }
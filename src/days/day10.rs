@@ -1,10 +1,12 @@
 use std::collections::HashSet;
 
-use crate::helpers::grid2d::{Direction4Way, Position2D, parse_str_grid};
+use crate::helpers::{
+    grid2d::{parse_grid_markers, Direction4Way, GridCell, Position2D, UnknownCell},
+    polygon::{self, Winding},
+};
 
 use super::day::Day;
-use anyhow::Result;
-use itertools::Itertools;
+use anyhow::{bail, Result};
 use ndarray::Array2;
 
 type Pos = Position2D<usize>;
@@ -21,10 +23,10 @@ pub enum Pipe {
     Ground,
     Start,
 }
-impl Pipe {
-    fn from_char(c: char) -> Self {
+impl GridCell for Pipe {
+    fn try_from_char(c: char) -> Result<Self, UnknownCell> {
         use Pipe::*;
-        match c {
+        Ok(match c {
             '|' => Vertical,
             '-' => Horizontal,
             'L' => NE,
@@ -33,9 +35,33 @@ impl Pipe {
             'F' => SE,
             '.' => Ground,
             'S' => Start,
-            _ => panic!(),
+            _ => return Err(UnknownCell(c)),
+        })
+    }
+    fn to_char(&self) -> char {
+        use Pipe::*;
+        match self {
+            Vertical => '|',
+            Horizontal => '-',
+            NE => 'L',
+            NW => 'J',
+            SW => '7',
+            SE => 'F',
+            Ground => '.',
+            Start => 'S',
         }
     }
+}
+impl Pipe {
+    /// Every pipe that joins two directions.
+    const PIPES: [Self; 6] = [
+        Self::Vertical,
+        Self::Horizontal,
+        Self::NE,
+        Self::NW,
+        Self::SW,
+        Self::SE,
+    ];
     const fn format(self) -> char {
         use Pipe::*;
         match self {
@@ -69,107 +95,130 @@ impl Pipe {
 
 #[derive(Debug, Clone)]
 pub struct Maze {
-    maze: Array2<Pipe>,
-    start: Pos,
+    pipes: Array2<Pipe>,
+    /// Positions of the loop in walking order, starting from the start
+    path: Vec<Pos>,
 }
 impl Maze {
-    #[allow(dead_code)]
-    fn format(&self) -> String {
-        use std::fmt::Write;
-        let mut output = String::new();
-        for line in self.maze.outer_iter() {
-            for pipe in line {
-                let _ = write!(output, "{}", pipe.format());
+    /// Replaces the start with whichever pipe joins both its neighbours into a closed loop,
+    /// trying each one in turn when more than two neighbours point at the start.
+    pub fn new(mut pipes: Array2<Pipe>, start: Pos) -> Result<Self> {
+        let connected = |dir: Dir| {
+            pipes
+                .get((start + dir).yx())
+                .is_some_and(|pipe| pipe.connects_to(dir.turn_right(2)))
+        };
+        let candidates: Vec<Pipe> = Pipe::PIPES
+            .into_iter()
+            .filter(|pipe| {
+                Dir::EVERY
+                    .into_iter()
+                    .all(|dir| !pipe.connects_to(dir) || connected(dir))
+            })
+            .collect();
+        for pipe in candidates {
+            pipes[start.yx()] = pipe;
+            if let Some(path) = Self::trace_loop(&pipes, start) {
+                return Ok(Self { pipes, path });
             }
-            let _ = writeln!(output);
         }
-        output
+        bail!("No pipe at the start closes a loop")
     }
-    fn fix_start(&mut self) {
-        let pipes: (bool, bool, bool, bool) = Dir::EVERY
-            .iter()
-            .map(|dir| {
-                self.maze
-                    .get((self.start + *dir).yx())
-                    .map_or(false, |pipe| pipe.connects_to(dir.turn_right(2)))
-            })
-            .collect_tuple()
-            .unwrap();
-        // North, East, South, West
-        let start_pipe = match pipes {
-            (true, true, false, false) => Pipe::NE,
-            (true, false, false, true) => Pipe::NW,
-            (false, false, true, true) => Pipe::SW,
-            (false, true, true, false) => Pipe::SE,
-            (true, false, true, false) => Pipe::Vertical,
-            (false, true, false, true) => Pipe::Horizontal,
-            _ => panic!("Invalid start pipe"),
-        };
-
-        *self.maze.get_mut(self.start.yx()).unwrap() = start_pipe;
+    /// Follows the pipes from `start` until getting back to it, if they do lead back.
+    fn trace_loop(pipes: &Array2<Pipe>, start: Pos) -> Option<Vec<Pos>> {
+        let mut path = vec![start];
+        let mut dir = Dir::EVERY
+            .into_iter()
+            .find(|&dir| pipes[start.yx()].connects_to(dir))?;
+        let mut pos = start + dir;
+        while pos != start {
+            let pipe = pipes.get(pos.yx())?;
+            let from = dir.turn_right(2);
+            if !pipe.connects_to(from) {
+                return None;
+            }
+            path.push(pos);
+            dir = Dir::EVERY
+                .into_iter()
+                .find(|&dir| dir != from && pipe.connects_to(dir))?;
+            pos = pos + dir;
+        }
+        // The start pipe has to take us back in the way we came
+        pipes[start.yx()]
+            .connects_to(dir.turn_right(2))
+            .then_some(path)
+    }
+    /// Positions of the loop in walking order, starting from the start.
+    #[allow(dead_code)]
+    pub fn loop_path(&self) -> &[Pos] {
+        &self.path
     }
-    fn walk(&self, visited: &HashSet<Pos>, from: Pos) -> Option<Pos> {
-        let pipe = self.maze.get(from.yx()).unwrap();
-        Dir::EVERY
+    /// Steps to the point of the loop farthest from the start.
+    pub const fn max_steps(&self) -> usize {
+        self.path.len() / 2
+    }
+    /// Which way round the loop goes when walked from the start.
+    #[allow(dead_code, clippy::cast_possible_wrap)]
+    pub fn orientation(&self) -> Winding {
+        let vertices: Vec<Position2D<isize>> = self
+            .path
             .iter()
-            .filter(|&&dir| pipe.connects_to(dir))
-            .map(|&dir| from + dir)
-            .find(|pos| !visited.contains(pos))
+            .map(|pos| Position2D::new(pos.x as isize, pos.y as isize))
+            .collect();
+        polygon::winding(&vertices).expect("Loops always enclose some area")
     }
-    fn step_through(&self) -> (usize, HashSet<Pos>) {
-        let pipe = self.maze.get(self.start.yx()).unwrap();
-        let mut positions: [Pos; 2] = Dir::EVERY
+    /// Same as `enclosed().len()`, treating the loop as a polygon for Pick's theorem.
+    #[allow(dead_code, clippy::cast_possible_wrap, clippy::cast_sign_loss)]
+    fn count_inner_spaces_pick(&self) -> usize {
+        let vertices: Vec<Position2D<isize>> = self
+            .path
             .iter()
-            .filter(|&&dir| pipe.connects_to(dir))
-            .map(|&dir| self.start + dir)
-            .collect::<Vec<Pos>>()
-            .try_into()
-            .unwrap();
-
-        let mut visited = HashSet::from([self.start, positions[0], positions[1]]);
-        let mut steps = 1;
-
-        loop {
-            steps += 1;
-            for position in &mut positions {
-                if let Some(new_position) = self.walk(&visited, *position) {
-                    *position = new_position;
-                    visited.insert(new_position);
-                } else {
-                    return (steps, visited);
+            .map(|pos| Position2D::new(pos.x as isize, pos.y as isize))
+            .collect();
+        polygon::interior_points(&vertices) as usize
+    }
+    /// Every tile inside the loop that isn't part of it.
+    pub fn enclosed(&self) -> HashSet<Pos> {
+        let on_loop: HashSet<Pos> = self.path.iter().copied().collect();
+        let mut enclosed = HashSet::new();
+        for (y, line) in self.pipes.outer_iter().enumerate() {
+            // Crossing a pipe with a northern end flips between outside and inside
+            let mut is_in = false;
+            for (x, pipe) in line.iter().enumerate() {
+                let pos = Pos::new(x, y);
+                if on_loop.contains(&pos) {
+                    if pipe.connects_to(Dir::North) {
+                        is_in = !is_in;
+                    }
+                } else if is_in {
+                    enclosed.insert(pos);
                 }
             }
         }
+        enclosed
     }
-    fn max_steps(&self) -> usize {
-        self.step_through().0
-    }
-    fn count_inner_spaces(&self) -> usize {
-        let visited = self.step_through().1;
-        self.maze
-            .outer_iter()
-            .enumerate()
-            .map(|(y, line)| {
-                let mut is_in = false;
-                line.iter()
-                    .enumerate()
-                    .filter(|&(x, pipe)| {
-                        let pos = Pos::new(x, y);
-                        if visited.contains(&pos) {
-                            match pipe {
-                                Pipe::Vertical | Pipe::NE | Pipe::NW => {
-                                    is_in = !is_in;
-                                }
-                                _ => {}
-                            }
-                            false
-                        } else {
-                            is_in
-                        }
-                    })
-                    .count()
-            })
-            .sum()
+    /// The loop drawn with box characters, with `I` on tiles inside it and `O` outside.
+    #[allow(dead_code)]
+    pub fn render(&self) -> String {
+        use std::fmt::Write;
+        let on_loop: HashSet<Pos> = self.path.iter().copied().collect();
+        let enclosed = self.enclosed();
+        let mut output = String::new();
+        for (y, line) in self.pipes.outer_iter().enumerate() {
+            for (x, pipe) in line.iter().enumerate() {
+                let pos = Pos::new(x, y);
+                let c = if on_loop.contains(&pos) {
+                    pipe.format()
+                } else if enclosed.contains(&pos) {
+                    'I'
+                } else {
+                    'O'
+                };
+                let _ = write!(output, "{c}");
+            }
+            let _ = writeln!(output);
+        }
+        output
     }
 }
 
@@ -179,22 +228,15 @@ impl Day for Day10 {
     type Output = usize;
 
     fn parse(input: String) -> Result<Self::Parsed> {
-        let maze = parse_str_grid(&input, Pipe::from_char)?;
-        let start = Pos::new_yx(
-            maze.indexed_iter()
-                .find(|(_, pipe)| **pipe == Pipe::Start)
-                .unwrap()
-                .0,
-        );
-        Ok(Maze { maze, start })
+        let (maze, markers) = parse_grid_markers(&input, &['S'])?;
+        let start = markers.single('S')?;
+        Maze::new(maze, start)
     }
-    fn first(mut maze: Self::Parsed) -> Self::Output {
-        maze.fix_start();
+    fn first(maze: Self::Parsed) -> Self::Output {
         maze.max_steps()
     }
-    fn second(mut maze: Self::Parsed) -> Self::Output {
-        maze.fix_start();
-        maze.count_inner_spaces()
+    fn second(maze: Self::Parsed) -> Self::Output {
+        maze.enclosed().len()
     }
 }
 
@@ -266,4 +308,54 @@ L7JLJL-JLJLJL--JLJ.L";
         assert_eq!(Day10::second(parsed4()), 8);
         assert_eq!(Day10::second(parsed5()), 10);
     }
+    #[test]
+    fn part2_pick() {
+        for (maze, expected) in [(parsed3(), 4), (parsed4(), 8), (parsed5(), 10)] {
+            assert_eq!(maze.loop_path().len(), maze.max_steps() * 2);
+            assert_eq!(maze.count_inner_spaces_pick(), expected);
+        }
+    }
+    #[test]
+    fn loop_api() {
+        let maze = parsed();
+        assert_eq!(
+            maze.loop_path()[..3],
+            [Pos::new(1, 1), Pos::new(2, 1), Pos::new(3, 1)]
+        );
+        assert_eq!(maze.orientation(), Winding::Clockwise);
+        assert_eq!(maze.enclosed(), HashSet::from([Pos::new(2, 2)]));
+        assert_eq!(
+            maze.render(),
+            "OOOOO
+O┌─┐O
+O│I│O
+O└─┘O
+OOOOO
+"
+        );
+        // Leaving the start the other way round
+        let maze = Day10::parse(INPUT.replace("S-7", "F-7").replace("L-J", "L-S")).unwrap();
+        assert_eq!(maze.orientation(), Winding::CounterClockwise);
+    }
+    #[test]
+    fn ambiguous_start() {
+        // Every neighbour of S points at it, but only going up and right closes a loop
+        let maze = Day10::parse(
+            ".....
+.F-7.
+.|.|.
+-S-J.
+.|..."
+                .to_string(),
+        )
+        .unwrap();
+        assert_eq!(maze.max_steps(), 4);
+        assert_eq!(maze.enclosed().len(), 1);
+        // S only closes the loop as an L, even though | would also fit its neighbours
+        assert_eq!(
+            maze.render(),
+            "OOOOO\nO┌─┐O\nO│I│O\nO└─┘O\nOOOOO\n"
+        );
+        assert!(Day10::parse(".S-\n.|.".to_string()).is_err());
+    }
 }
@@ -1,33 +1,104 @@
-use std::collections::HashSet;
-
 use crate::helpers::grid2d::Position2D;
 
 use super::day::Day;
 use anyhow::Result;
-use itertools::Itertools;
+
+type Pos = Position2D<usize>;
 
 #[derive(Debug, Clone)]
 pub struct Universe {
-    galaxies: Vec<Position2D<usize>>,
+    galaxies: Vec<Pos>,
+    /// Columns and rows without any galaxies, in order
     empty_x: Vec<usize>,
     empty_y: Vec<usize>,
 }
 impl Universe {
-    fn expand(&mut self, factor: usize) {
-        for galaxy in &mut self.galaxies {
-            let add_x = self.empty_x.iter().filter(|&&ex| ex < galaxy.x).count();
-            let add_y = self.empty_y.iter().filter(|&&ey| ey < galaxy.y).count();
+    /// Galaxy positions once every empty row and column has grown to `factor` of them.
+    ///
+    /// # Panics
+    /// If `factor` is 0, since empty rows and columns can't disappear entirely.
+    pub fn expand(&self, factor: usize) -> Expanded {
+        assert!(factor >= 1, "Expansion factor must be at least 1");
+        let grow = |value: usize, empty: &[usize]| {
+            value + empty.partition_point(|&e| e < value) * (factor - 1)
+        };
+        Expanded::new(
+            self.galaxies
+                .iter()
+                .map(|galaxy| {
+                    Pos::new(grow(galaxy.x, &self.empty_x), grow(galaxy.y, &self.empty_y))
+                })
+                .collect(),
+        )
+    }
+}
 
-            *galaxy = galaxy.add_x(add_x * factor).add_y(add_y * factor);
-        }
+/// Coordinates along one axis, sorted and with running totals for summing distances.
+#[derive(Debug, Clone)]
+struct Axis {
+    sorted: Vec<usize>,
+    /// `prefix[i]` is the sum of the first `i` sorted values
+    prefix: Vec<usize>,
+}
+impl Axis {
+    fn new(mut sorted: Vec<usize>) -> Self {
+        sorted.sort_unstable();
+        let prefix = std::iter::once(0)
+            .chain(sorted.iter().scan(0, |sum, &value| {
+                *sum += value;
+                Some(*sum)
+            }))
+            .collect();
+        Self { sorted, prefix }
     }
-    fn distances(&self) -> usize {
-        self.galaxies
+    /// Sum of the distances between every pair of values.
+    fn pair_sum(&self) -> usize {
+        self.sorted
             .iter()
-            .tuple_combinations()
-            .map(|(a, b)| a.manhattan(b))
+            .enumerate()
+            .map(|(i, &value)| value * i - self.prefix[i])
             .sum()
     }
+    /// Sum of the distances from `value` to every value.
+    fn distances_from(&self, value: usize) -> usize {
+        let below = self.sorted.partition_point(|&v| v < value);
+        let total = self.prefix[self.sorted.len()];
+        (value * below - self.prefix[below])
+            + (total - self.prefix[below] - value * (self.sorted.len() - below))
+    }
+}
+
+/// Galaxies after expansion.
+#[derive(Debug, Clone)]
+pub struct Expanded {
+    #[allow(dead_code)]
+    galaxies: Vec<Pos>,
+    x: Axis,
+    y: Axis,
+}
+impl Expanded {
+    fn new(galaxies: Vec<Pos>) -> Self {
+        Self {
+            x: Axis::new(galaxies.iter().map(|galaxy| galaxy.x).collect()),
+            y: Axis::new(galaxies.iter().map(|galaxy| galaxy.y).collect()),
+            galaxies,
+        }
+    }
+    #[allow(dead_code)]
+    pub fn galaxies(&self) -> &[Pos] {
+        &self.galaxies
+    }
+    /// Sum of the manhattan distances between every pair of galaxies. The axes can be
+    /// summed separately, and once sorted every value is simply larger than the ones before.
+    pub fn distance_sum(&self) -> usize {
+        self.x.pair_sum() + self.y.pair_sum()
+    }
+    /// Sum of the manhattan distances from galaxy `index` to all the others.
+    #[allow(dead_code)]
+    pub fn distances_from(&self, index: usize) -> usize {
+        let galaxy = self.galaxies[index];
+        self.x.distances_from(galaxy.x) + self.y.distances_from(galaxy.y)
+    }
 }
 
 pub struct Day11;
@@ -36,43 +107,47 @@ impl Day for Day11 {
     type Output = usize;
 
     fn parse(input: String) -> Result<Self::Parsed> {
-        let width = input.lines().next().unwrap().len();
+        // Rows don't have to be the same length, so make room for the longest
+        let width = input
+            .lines()
+            .map(|line| line.chars().count())
+            .max()
+            .unwrap_or(0);
+        let height = input.lines().count();
 
-        let mut empty_y = HashSet::new();
-        let mut empty_x: HashSet<usize> = (0..width).collect();
         let mut galaxies = Vec::new();
+        let mut used_x = vec![false; width];
+        let mut used_y = vec![false; height];
         for (y, line) in input.lines().enumerate() {
-            empty_y.insert(y);
             for (x, char) in line.chars().enumerate() {
                 if char == '#' {
-                    empty_x.remove(&x);
-                    empty_y.remove(&y);
-                    galaxies.push(Position2D::new(x, y));
+                    used_x[x] = true;
+                    used_y[y] = true;
+                    galaxies.push(Pos::new(x, y));
                 }
             }
         }
+        let empty = |used: Vec<bool>| (0..used.len()).filter(|&i| !used[i]).collect();
 
         Ok(Universe {
             galaxies,
-            empty_x: empty_x.drain().collect(),
-            empty_y: empty_y.drain().collect(),
+            empty_x: empty(used_x),
+            empty_y: empty(used_y),
         })
     }
-    fn first(mut universe: Self::Parsed) -> Self::Output {
-        universe.expand(1);
-
-        universe.distances()
+    fn first(universe: Self::Parsed) -> Self::Output {
+        universe.expand(2).distance_sum()
     }
-    fn second(mut universe: Self::Parsed) -> Self::Output {
-        universe.expand(1_000_000 - 1);
-
-        universe.distances()
+    fn second(universe: Self::Parsed) -> Self::Output {
+        universe.expand(1_000_000).distance_sum()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::helpers::test_rng::XorShift;
+    use itertools::Itertools;
     const INPUT: &str = "...#......
 .......#..
 #.........
@@ -92,6 +167,78 @@ mod tests {
     }
     #[test]
     fn part2() {
-        assert_eq!(Day11::second(parsed()), 82000210);
+        assert_eq!(Day11::second(parsed()), 82_000_210);
+    }
+
+    /// The previous solution, moving each galaxy and then summing every pair.
+    fn quadratic(universe: &Universe, factor: usize) -> usize {
+        let galaxies: Vec<Pos> = universe
+            .galaxies
+            .iter()
+            .map(|galaxy| {
+                let add_x = universe.empty_x.iter().filter(|&&ex| ex < galaxy.x).count();
+                let add_y = universe.empty_y.iter().filter(|&&ey| ey < galaxy.y).count();
+                galaxy
+                    .add_x(add_x * (factor - 1))
+                    .add_y(add_y * (factor - 1))
+            })
+            .collect();
+        galaxies
+            .iter()
+            .tuple_combinations()
+            .map(|(a, b)| a.manhattan(b))
+            .sum()
+    }
+    #[test]
+    fn against_quadratic() {
+        let mut rng = XorShift::new(11);
+        let random: Vec<Universe> = (0..5)
+            .map(|_| {
+                let input = (0..40)
+                    .map(|_| {
+                        (0..40)
+                            .map(|_| if rng.range(0..30) == 0 { '#' } else { '.' })
+                            .collect::<String>()
+                    })
+                    .join("\n");
+                Day11::parse(input).unwrap()
+            })
+            .collect();
+        for universe in random.iter().chain([&parsed()]) {
+            for factor in [2, 10, 100, 1_000_000] {
+                let expanded = universe.expand(factor);
+                assert_eq!(expanded.distance_sum(), quadratic(universe, factor));
+                let from_each: usize = (0..expanded.galaxies().len())
+                    .map(|i| expanded.distances_from(i))
+                    .sum();
+                assert_eq!(from_each, 2 * expanded.distance_sum());
+            }
+        }
+    }
+    #[test]
+    fn single_galaxy() {
+        let expanded = parsed().expand(10);
+        // Galaxy 5 to galaxy 9 is 7 steps without expansion, crossing one empty row and one
+        // empty column
+        let (a, b) = (expanded.galaxies()[4], expanded.galaxies()[8]);
+        assert_eq!(a.manhattan(&b), 7 + 2 * 9);
+        let by_hand: usize = expanded.galaxies().iter().map(|g| g.manhattan(&a)).sum();
+        assert_eq!(expanded.distances_from(4), by_hand);
+
+        // A factor of 1 leaves the universe as it is
+        let same = parsed().expand(1);
+        assert_eq!(same.galaxies(), parsed().galaxies);
+    }
+    #[test]
+    fn ragged_rows() {
+        let universe = Day11::parse("#.\n...#\n.".to_string()).unwrap();
+        assert_eq!(universe.empty_x, [1, 2]);
+        assert_eq!(universe.empty_y, [2]);
+        assert_eq!(universe.expand(2).distance_sum(), 6);
+    }
+    #[test]
+    #[should_panic(expected = "Expansion factor must be at least 1")]
+    fn no_expansion() {
+        parsed().expand(0);
     }
 }
@@ -1,7 +1,10 @@
-use std::collections::HashMap;
+use crate::helpers::{
+    grid2d::GridCell,
+    nonogram::{self, Cell},
+};
 
 use super::day::Day;
-use anyhow::Result;
+use anyhow::{ensure, Context, Result};
 use itertools::Itertools;
 use rayon::iter::IntoParallelRefIterator;
 use rayon::iter::IntoParallelRefMutIterator;
@@ -9,80 +12,28 @@ use rayon::iter::ParallelIterator;
 use smallvec::smallvec;
 use smallvec::SmallVec;
 
-#[derive(Clone, Debug, Copy, PartialEq, Eq)]
-pub enum Location {
-    Operational,
-    Damaged,
-    Unknown,
-}
-impl Location {
-    fn from_char(c: char) -> Self {
-        match c {
-            '.' => Self::Operational,
-            '#' => Self::Damaged,
-            '?' => Self::Unknown,
-            _ => panic!(),
-        }
-    }
-}
+/// A single row of a nonogram, with `.` for working springs and `#` for damaged ones.
 #[derive(Clone, Debug)]
 pub struct SpringRow {
-    springs: SmallVec<[Location; 128]>,
+    springs: SmallVec<[Cell; 128]>,
     counts: SmallVec<[usize; 32]>,
 }
 
 impl SpringRow {
-    fn poss(
-        &self,
-        cache: &mut HashMap<(usize, usize, usize), usize>,
-        position: usize,
-        block_pos: usize,
-        block_len: usize,
-    ) -> usize {
-        use Location::*;
-        let key = (position, block_pos, block_len);
-        if let Some(val) = cache.get(&key) {
-            return *val;
-        }
-
-        if position == self.springs.len() {
-            if (block_pos == self.counts.len() && block_len == 0)
-                || (block_pos == self.counts.len() - 1 && self.counts[block_pos] == block_len)
-            {
-                return 1;
-            }
-            return 0;
-        }
-
-        let mut sum = 0;
-        if self.springs[position] == Damaged || self.springs[position] == Unknown {
-            sum += self.poss(cache, position + 1, block_pos, block_len + 1);
-        }
-        if self.springs[position] == Operational || self.springs[position] == Unknown {
-            if block_len == 0 {
-                sum += self.poss(cache, position + 1, block_pos, 0);
-            } else if block_len > 0
-                && block_pos < self.counts.len()
-                && self.counts[block_pos] == block_len
-            {
-                sum += self.poss(cache, position + 1, block_pos + 1, 0);
-            }
-        }
-
-        cache.insert(key, sum);
-        sum
-    }
     fn possibilities(&self) -> usize {
-        self.poss(&mut HashMap::new(), 0, 0, 0)
+        nonogram::count_line(&self.springs, &self.counts)
+            .expect("Group sizes are checked while parsing")
     }
     fn expand(&mut self) {
-        self.springs = (0..9).map(|n| {
-            if n % 2 == 0 {
-                self.springs.clone()
-            } else {
-                smallvec![Location::Unknown]
-            }
-        }).concat();
+        self.springs = (0..9)
+            .map(|n| {
+                if n % 2 == 0 {
+                    self.springs.clone()
+                } else {
+                    smallvec![Cell::Unknown]
+                }
+            })
+            .concat();
 
         self.counts = self.counts.repeat(5).into();
     }
@@ -94,18 +45,27 @@ impl Day for Day12 {
     type Output = usize;
 
     fn parse(input: String) -> Result<Self::Parsed> {
-        Ok(input
+        input
             .lines()
             .map(|line| {
-                let (springs, counts) = line.split_once(' ').unwrap();
-                let springs = springs.chars().map(Location::from_char).collect();
-                let counts = counts
+                let (springs, counts) = line.split_once(' ').context("Missing counts")?;
+                let springs = springs
+                    .chars()
+                    .map(Cell::try_from_char)
+                    .collect::<Result<_, _>>()?;
+                let counts: SmallVec<[usize; 32]> = counts
                     .split(',')
-                    .filter_map(|count| count.parse().ok())
-                    .collect();
-                SpringRow { springs, counts }
+                    .map(str::parse)
+                    .collect::<Result<_, _>>()?;
+                ensure!(
+                    !counts.contains(&0),
+                    "Groups of damaged springs can't be empty"
+                );
+                Ok(SpringRow { springs, counts })
             })
-            .collect())
+            .enumerate()
+            .map(|(i, row)| row.with_context(|| format!("Invalid row {}", i + 1)))
+            .collect()
     }
     fn first(rows: Self::Parsed) -> Self::Output {
         rows.par_iter().map(SpringRow::possibilities).sum()
@@ -138,6 +98,14 @@ mod tests {
     }
     #[test]
     fn part2() {
-        assert_eq!(Day12::second(parsed()), 525152);
+        assert_eq!(Day12::second(parsed()), 525_152);
+    }
+    #[test]
+    fn empty_group() {
+        let error = Day12::parse("???.### 1,1,3\n#.#?? 1,0,2".to_string()).unwrap_err();
+        assert_eq!(
+            format!("{error:#}"),
+            "Invalid row 2: Groups of damaged springs can't be empty"
+        );
     }
 }
@@ -1,10 +1,9 @@
-use std::{
-    collections::{hash_map::Entry, HashMap},
-    fmt,
-    ops::Range,
-};
+use std::ops::Range;
 
-use crate::helpers::grid2d::{parse_str_grid, Direction4Way};
+use crate::helpers::{
+    cycle,
+    grid2d::{format_grid, parse_grid, Direction4Way, GridCell, UnknownCell},
+};
 
 use super::day::Day;
 use anyhow::Result;
@@ -19,23 +18,26 @@ pub enum Place {
     CubeRock,
     Empty,
 }
-impl fmt::Display for Place {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+impl GridCell for Place {
+    fn try_from_char(c: char) -> Result<Self, UnknownCell> {
+        match c {
+            'O' => Ok(Self::RoundRock),
+            '#' => Ok(Self::CubeRock),
+            '.' => Ok(Self::Empty),
+            _ => Err(UnknownCell(c)),
+        }
+    }
+    fn to_char(&self) -> char {
         match self {
-            Self::RoundRock => write!(f, "O"),
-            Self::CubeRock => write!(f, "#"),
-            Self::Empty => write!(f, "."),
+            Self::RoundRock => 'O',
+            Self::CubeRock => '#',
+            Self::Empty => '.',
         }
     }
 }
 #[allow(dead_code)]
 fn print_dish(dish: &Array2<Place>) {
-    for slice in dish.axis_iter(Axis(0)) {
-        for p in slice {
-            print!("{p}");
-        }
-        println!();
-    }
+    print!("{}", format_grid(dish));
 }
 
 fn reversable_range(
@@ -105,37 +107,22 @@ impl Day for Day14 {
     type Output = usize;
 
     fn parse(input: String) -> Result<Self::Parsed> {
-        Ok(parse_str_grid(&input, |c| match c {
-            'O' => Place::RoundRock,
-            '#' => Place::CubeRock,
-            _ => Place::Empty,
-        })?)
+        Ok(parse_grid(&input)?)
     }
     fn first(mut dish: Self::Parsed) -> Self::Output {
         tilt(&mut dish, Direction4Way::North);
         calc_dish_load(&dish)
     }
-    fn second(mut dish: Self::Parsed) -> Self::Output {
-        let total_steps = 1_000_000_000;
-        let mut states = HashMap::new();
-        let mut step = 0;
-        while step < total_steps {
-            cycle(&mut dish);
-            step += 1;
-            match states.entry(dish.clone().into_raw_vec()) {
-                Entry::Occupied(entry) => {
-                    let loop_length = step - entry.get();
-                    step += ((total_steps - step) / loop_length) * loop_length;
-                    break;
-                }
-                Entry::Vacant(entry) => {
-                    entry.insert(step);
-                }
-            }
-        }
-        for _ in step..total_steps {
-            cycle(&mut dish);
-        }
+    fn second(dish: Self::Parsed) -> Self::Output {
+        let dish = cycle::state_at(
+            &dish,
+            |dish| {
+                let mut dish = dish.clone();
+                cycle(&mut dish);
+                dish
+            },
+            1_000_000_000,
+        );
         calc_dish_load(&dish)
     }
 }
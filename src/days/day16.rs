@@ -1,5 +1,5 @@
 use crate::helpers::{
-    grid2d::{parse_str_grid, Direction4Way, Position2D},
+    grid2d::{parse_grid, Direction4Way, GridCell, Position2D, UnknownCell},
     BitArray,
 };
 
@@ -20,16 +20,28 @@ pub enum Tile {
     SplitVert,   // |
     SplitHori,   // -
 }
-impl Tile {
-    const fn from_char(c: char) -> Self {
+impl GridCell for Tile {
+    fn try_from_char(c: char) -> Result<Self, UnknownCell> {
         match c {
-            '/' => Self::MirrorRight,
-            '\\' => Self::MirrorLeft,
-            '|' => Self::SplitVert,
-            '-' => Self::SplitHori,
-            _ => Self::Empty,
+            '.' => Ok(Self::Empty),
+            '/' => Ok(Self::MirrorRight),
+            '\\' => Ok(Self::MirrorLeft),
+            '|' => Ok(Self::SplitVert),
+            '-' => Ok(Self::SplitHori),
+            _ => Err(UnknownCell(c)),
+        }
+    }
+    fn to_char(&self) -> char {
+        match self {
+            Self::Empty => '.',
+            Self::MirrorRight => '/',
+            Self::MirrorLeft => '\\',
+            Self::SplitVert => '|',
+            Self::SplitHori => '-',
         }
     }
+}
+impl Tile {
     const fn travel(self, dir: Dir) -> (Dir, Option<Dir>) {
         match (self, dir) {
             (Self::MirrorRight, Dir::East) | (Self::MirrorLeft, Dir::West) => (Dir::North, None),
@@ -83,7 +95,7 @@ impl Day for Day16 {
     type Output = usize;
 
     fn parse(input: String) -> Result<Self::Parsed> {
-        Ok(parse_str_grid(&input, Tile::from_char)?)
+        Ok(parse_grid(&input)?)
     }
     fn first(tiles: Self::Parsed) -> Self::Output {
         Lazer::simulate_lazer(&tiles, Lazer::new(Pos::new(0, 0), Dir::East))
@@ -1,4 +1,4 @@
-use crate::helpers::grid2d::{parse_str_grid, Direction4Way, Position2D};
+use crate::helpers::grid2d::{try_parse_str_grid, Direction4Way, Position2D};
 
 use super::day::Day;
 use anyhow::Result;
@@ -98,7 +98,7 @@ impl Day for Day17 {
     type Output = u32;
 
     fn parse(input: String) -> Result<Self::Parsed> {
-        Ok(parse_str_grid(&input, |c| c.to_digit(10).unwrap())?)
+        Ok(try_parse_str_grid(&input, |c| c.to_digit(10))?)
     }
     fn first(grid: Self::Parsed) -> Self::Output {
         let end_pos = Pos::new_yx((grid.shape()[0] - 1, grid.shape()[1] - 1));
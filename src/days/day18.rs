@@ -1,9 +1,11 @@
 #![allow(clippy::cast_possible_wrap)]
-use crate::helpers::grid2d::{Direction4Way, Position2D};
+use crate::helpers::{
+    grid2d::{Direction4Way, Position2D},
+    polygon,
+};
 
 use super::day::Day;
 use anyhow::Result;
-use itertools::Itertools;
 
 type Dir = Direction4Way;
 type Pos = Position2D<isize>;
@@ -22,24 +24,18 @@ fn pos_add(pos: Pos, dir: Dir, steps: isize) -> Pos {
     }
 }
 
-fn path_area(instructions: &Vec<Instruction>) -> isize {
-    let mut pos = Pos::new(0, 0);
-
-    let mut path = vec![pos];
-    let mut distance = 0;
-    for instruction in instructions {
-        distance += instruction.steps;
-        pos = pos_add(pos, instruction.dir, instruction.steps);
-        path.push(pos);
-    }
-
-    let area = path
+fn corners(instructions: &[Instruction]) -> Vec<Pos> {
+    instructions
         .iter()
-        .tuple_windows()
-        .map(|(a, b)| a.x * b.y - a.y * b.x)
-        .sum::<isize>();
+        .scan(Pos::new(0, 0), |pos, instruction| {
+            *pos = pos_add(*pos, instruction.dir, instruction.steps);
+            Some(*pos)
+        })
+        .collect()
+}
 
-    ((area.abs() + distance) / 2) + 1
+fn path_area(instructions: &[Instruction]) -> isize {
+    polygon::lattice_points(&corners(instructions))
 }
 
 pub struct Day18;
@@ -112,6 +108,16 @@ U 2 (#7a21e3)";
     }
     #[test]
     fn part2() {
-        assert_eq!(Day18::second(parsed()), 952408144115);
+        assert_eq!(Day18::second(parsed()), 952_408_144_115);
+    }
+    #[test]
+    fn trench() {
+        let corners = corners(&parsed().0);
+        assert_eq!(polygon::boundary_points(&corners), 38);
+        assert_eq!(polygon::interior_points(&corners), 24);
+        assert_eq!(
+            polygon::winding(&corners),
+            Some(polygon::Winding::Clockwise)
+        );
     }
 }
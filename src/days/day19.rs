@@ -1,6 +1,7 @@
-use std::{cmp::Ordering, collections::HashMap};
+use std::cmp::Ordering;
 
 use super::day::Day;
+use crate::helpers::graph::Interner;
 use anyhow::Result;
 use smallvec::{smallvec, SmallVec};
 
@@ -25,23 +26,23 @@ impl CompType {
 #[derive(Debug, Clone)]
 pub enum Rule {
     Straight {
-        to: String,
+        to: u32,
     },
     Comparison {
         ct: CompType,
         comparison: Ordering,
         value: usize,
-        to: String,
+        to: u32,
     },
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct Workflow(Vec<Rule>);
 impl Workflow {
-    fn filter(&self, part: &Part) -> String {
+    fn filter(&self, part: &Part) -> u32 {
         for rule in &self.0 {
             match rule {
-                Rule::Straight { to } => return to.clone(),
+                Rule::Straight { to } => return *to,
                 Rule::Comparison {
                     ct,
                     comparison,
@@ -50,18 +51,18 @@ impl Workflow {
                 } => {
                     let part_val = part[*ct as usize];
                     if part_val.cmp(value) == *comparison {
-                        return to.clone();
+                        return *to;
                     }
                 }
             }
         }
         panic!();
     }
-    fn ranges(&self, mut part: PartRange) -> SmallVec<[(String, PartRange); 4]> {
+    fn ranges(&self, mut part: PartRange) -> SmallVec<[(u32, PartRange); 4]> {
         let mut ranges = smallvec![];
         for rule in &self.0 {
             match rule {
-                Rule::Straight { to } => ranges.push((to.clone(), part)),
+                Rule::Straight { to } => ranges.push((*to, part)),
                 Rule::Comparison {
                     ct,
                     comparison,
@@ -75,12 +76,12 @@ impl Workflow {
                             if range.0 < *value {
                                 if range.1 - 1 < *value {
                                     // Full range matches comparison
-                                    ranges.push((to.clone(), part));
+                                    ranges.push((*to, part));
                                     return ranges;
                                 }
                                 let mut left = part;
                                 left[index] = (range.0, *value);
-                                ranges.push((to.clone(), left));
+                                ranges.push((*to, left));
                                 part[index] = (*value, range.1);
                             }
                         }
@@ -88,13 +89,13 @@ impl Workflow {
                             if range.1 - 1 > *value {
                                 if range.0 > *value {
                                     // Full range matches comparison
-                                    ranges.push((to.clone(), part));
+                                    ranges.push((*to, part));
                                     return ranges;
                                 }
                                 let mut right = part;
                                 let value = *value + 1;
                                 right[index] = (value, range.1);
-                                ranges.push((to.clone(), right));
+                                ranges.push((*to, right));
                                 part[index] = (range.0, value);
                             }
                         }
@@ -110,14 +111,27 @@ impl Workflow {
 type Part = [usize; 4];
 type PartRange = [(usize, usize); 4];
 
+/// Workflows indexed by the id of their name. Parts sent to `A` or `R` are done.
+#[derive(Debug, Clone)]
+pub struct Workflows {
+    flows: Vec<Workflow>,
+    start: u32,
+    accepted: u32,
+    rejected: u32,
+}
+
 pub struct Day19;
 impl Day for Day19 {
-    type Parsed = (HashMap<String, Workflow>, Vec<Part>);
+    type Parsed = (Workflows, Vec<Part>);
     type Output = usize;
 
     fn parse(input: String) -> Result<Self::Parsed> {
         let (workflows, parts) = input.split_once("\n\n").unwrap();
-        let workflows = workflows
+        let mut labels = Interner::new();
+        let start = labels.intern("in");
+        let accepted = labels.intern("A");
+        let rejected = labels.intern("R");
+        let named_flows: Vec<(u32, Workflow)> = workflows
             .lines()
             .map(|line| {
                 let (id, rules) = line.split_once('{').unwrap();
@@ -133,18 +147,28 @@ impl Day for Day19 {
                                     _ => panic!(),
                                 },
                                 value: left[2..].parse().unwrap(),
-                                to: to.to_string(),
+                                to: labels.intern(to),
                             }
                         } else {
                             Rule::Straight {
-                                to: rule.to_string(),
+                                to: labels.intern(rule),
                             }
                         }
                     })
                     .collect();
-                (id.to_string(), Workflow(rules))
+                (labels.intern(id), Workflow(rules))
             })
             .collect();
+        let mut flows = vec![Workflow::default(); labels.len()];
+        for (id, workflow) in named_flows {
+            flows[id as usize] = workflow;
+        }
+        let workflows = Workflows {
+            flows,
+            start,
+            accepted,
+            rejected,
+        };
         let parts = parts
             .lines()
             .map(|line| {
@@ -163,12 +187,11 @@ impl Day for Day19 {
         parts
             .into_iter()
             .map(|part| {
-                let mut bucket = "in".to_string();
-                while !(bucket == "A" || bucket == "R") {
-                    let workflow = &workflows[&bucket];
-                    bucket = workflow.filter(&part);
+                let mut bucket = workflows.start;
+                while !(bucket == workflows.accepted || bucket == workflows.rejected) {
+                    bucket = workflows.flows[bucket as usize].filter(&part);
                 }
-                if bucket == "A" {
+                if bucket == workflows.accepted {
                     part.iter().sum::<usize>()
                 } else {
                     0
@@ -177,23 +200,26 @@ impl Day for Day19 {
             .sum()
     }
     fn second((workflows, _): Self::Parsed) -> Self::Output {
-        let mut part_ranges: SmallVec<[(String, PartRange); 16]> =
-            smallvec![("in".to_string(), [(1, 4001); 4])];
+        let mut part_ranges: SmallVec<[(u32, PartRange); 16]> =
+            smallvec![(workflows.start, [(1, 4001); 4])];
         let mut combinations = 0;
 
         while let Some((bucket, range)) = part_ranges.pop() {
-            part_ranges.extend(workflows[&bucket].ranges(range).into_iter().filter_map(
-                |(bucket, range)| {
-                    if bucket == "A" || bucket == "R" {
-                        if bucket == "A" {
-                            combinations += range.iter().map(|r| r.1 - r.0).product::<usize>();
+            part_ranges.extend(
+                workflows.flows[bucket as usize]
+                    .ranges(range)
+                    .into_iter()
+                    .filter_map(|(bucket, range)| {
+                        if bucket == workflows.accepted || bucket == workflows.rejected {
+                            if bucket == workflows.accepted {
+                                combinations += range.iter().map(|r| r.1 - r.0).product::<usize>();
+                            }
+                            None
+                        } else {
+                            Some((bucket, range))
                         }
-                        None
-                    } else {
-                        Some((bucket, range))
-                    }
-                },
-            ));
+                    }),
+            );
         }
         combinations
     }
@@ -228,6 +254,6 @@ hdj{m>838:A,pv}
     }
     #[test]
     fn part2() {
-        assert_eq!(Day19::second(parsed()), 167409079868000);
+        assert_eq!(Day19::second(parsed()), 167_409_079_868_000);
     }
 }
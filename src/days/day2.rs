@@ -1,77 +1,120 @@
+use std::collections::BTreeMap;
+
 use super::day::Day;
-use anyhow::Result;
+use anyhow::{Context, Result};
+
+/// What part 1 asks whether every game could have been played with.
+const PART1_BAG: [(&str, usize); 3] = [("red", 12), ("green", 13), ("blue", 14)];
+/// Colours whose counts part 2 multiplies.
+const PART2_COLOURS: [&str; 3] = ["red", "green", "blue"];
+
+/// Number of cubes of each colour, with any colour not in the map having none.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Bag(BTreeMap<String, usize>);
+impl Bag {
+    pub fn count(&self, colour: &str) -> usize {
+        self.0.get(colour).copied().unwrap_or(0)
+    }
+    /// Smallest bag holding at least as many of every colour as both bags.
+    pub fn union(mut self, other: &Self) -> Self {
+        for (colour, &count) in &other.0 {
+            let entry = self.0.entry(colour.clone()).or_default();
+            *entry = (*entry).max(count);
+        }
+        self
+    }
+    /// Colours this has more of than `limit`, with how many more.
+    pub fn excess<'a>(&'a self, limit: &'a Self) -> impl Iterator<Item = (&'a str, usize)> + 'a {
+        self.0.iter().filter_map(|(colour, &count)| {
+            let excess = count.saturating_sub(limit.count(colour));
+            (excess > 0).then_some((colour.as_str(), excess))
+        })
+    }
+    /// Product of the counts of `colours`, which is 0 when any of them is missing.
+    pub fn power(&self, colours: &[&str]) -> usize {
+        colours.iter().map(|colour| self.count(colour)).product()
+    }
+}
+impl<'a> FromIterator<(&'a str, usize)> for Bag {
+    fn from_iter<T: IntoIterator<Item = (&'a str, usize)>>(iter: T) -> Self {
+        Self(
+            iter.into_iter()
+                .map(|(colour, count)| (colour.to_string(), count))
+                .collect(),
+        )
+    }
+}
 
 #[derive(Clone, Debug)]
 pub struct Game {
-    plays: Vec<Play>,
     id: usize,
+    /// Cubes shown at each reveal
+    draws: Vec<Bag>,
 }
-#[derive(Clone, Copy, Debug)]
-pub struct Play {
-    red: usize,
-    green: usize,
-    blue: usize,
+impl Game {
+    /// Fewest cubes of each colour the game could have been played with.
+    pub fn minimal_bag(&self) -> Bag {
+        self.draws.iter().fold(Bag::default(), Bag::union)
+    }
+    /// Colours the game shows more of than there are in `bag`, with how many are missing.
+    #[allow(dead_code)]
+    pub fn shortfall(&self, bag: &Bag) -> Vec<(String, usize)> {
+        self.minimal_bag()
+            .excess(bag)
+            .map(|(colour, missing)| (colour.to_string(), missing))
+            .collect()
+    }
+    pub fn is_feasible(&self, bag: &Bag) -> bool {
+        self.draws
+            .iter()
+            .all(|draw| draw.excess(bag).next().is_none())
+    }
 }
+
+/// Games that could have been played with `bag`.
+pub fn feasible_games<'a>(games: &'a [Game], bag: &'a Bag) -> impl Iterator<Item = &'a Game> {
+    games.iter().filter(|game| game.is_feasible(bag))
+}
+
+fn parse_game(line: &str) -> Result<Game> {
+    let (id, draws) = line.split_once(": ").context("Missing ': '")?;
+    let id = id
+        .strip_prefix("Game ")
+        .context("Missing 'Game'")?
+        .parse()?;
+    let draws = draws
+        .split("; ")
+        .map(|draw| {
+            draw.split(", ")
+                .map(|cubes| {
+                    let (count, colour) = cubes.split_once(' ').context("Missing colour")?;
+                    Ok((colour, count.parse()?))
+                })
+                .collect::<Result<Bag>>()
+        })
+        .collect::<Result<_>>()?;
+    Ok(Game { id, draws })
+}
+
 pub struct Day2;
 impl Day for Day2 {
     type Parsed = Vec<Game>;
     type Output = usize;
 
     fn parse(input: String) -> Result<Self::Parsed> {
-        Ok(input
+        input
             .lines()
-            .map(|line| {
-                let (id, games) = line.split_once(": ").unwrap();
-                let id = id.split(' ').find_map(|s| s.parse::<usize>().ok()).unwrap();
-                let games = games
-                    .split("; ")
-                    .map(|set| {
-                        let mut play = Play {
-                            red: 0,
-                            green: 0,
-                            blue: 0,
-                        };
-                        for one in set.split(", ") {
-                            let (num, color) = one.split_once(' ').unwrap();
-                            let num = num.parse::<usize>().unwrap();
-                            match color {
-                                "red" => play.red = num,
-                                "green" => play.green = num,
-                                "blue" => play.blue = num,
-                                _ => panic!(),
-                            }
-                        }
-                        play
-                    })
-                    .collect();
-                Game { plays: games, id }
-            })
-            .collect())
+            .map(|line| parse_game(line).with_context(|| format!("Invalid game: {line}")))
+            .collect()
     }
     fn first(games: Self::Parsed) -> Self::Output {
-        games
-            .iter()
-            .filter(|game| {
-                !game
-                    .plays
-                    .iter()
-                    .any(|play| play.red > 12 || play.green > 13 || play.blue > 14)
-            })
-            .map(|game| game.id)
-            .sum()
+        let bag = PART1_BAG.into_iter().collect();
+        feasible_games(&games, &bag).map(|game| game.id).sum()
     }
     fn second(games: Self::Parsed) -> Self::Output {
         games
             .iter()
-            .map(|game| {
-                [
-                    game.plays.iter().map(|play| play.red).max().unwrap(),
-                    game.plays.iter().map(|play| play.green).max().unwrap(),
-                    game.plays.iter().map(|play| play.blue).max().unwrap(),
-                ]
-                .iter()
-                .product::<usize>()
-            })
+            .map(|game| game.minimal_bag().power(&PART2_COLOURS))
             .sum()
     }
 }
@@ -95,4 +138,48 @@ Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green";
     fn part2() {
         assert_eq!(Day2::second(parsed()), 2286);
     }
+    #[test]
+    fn queries() {
+        let games = parsed();
+        let bag: Bag = PART1_BAG.into_iter().collect();
+        assert_eq!(
+            games[2].minimal_bag(),
+            [("red", 20), ("green", 13), ("blue", 6)]
+                .into_iter()
+                .collect()
+        );
+        assert_eq!(games[2].shortfall(&bag), [("red".to_string(), 8)]);
+        assert_eq!(
+            games[3].shortfall(&bag),
+            [("blue".to_string(), 1), ("red".to_string(), 2)]
+        );
+        assert!(games[0].shortfall(&bag).is_empty());
+
+        let games = Day2::parse(
+            "Game 7: 2 purple, 1 red; 3 orange
+Game 8: 1 purple"
+                .to_string(),
+        )
+        .unwrap();
+        let bag = [("purple", 1), ("orange", 5)].into_iter().collect();
+        let ids: Vec<usize> = feasible_games(&games, &bag).map(|game| game.id).collect();
+        assert_eq!(ids, [8]);
+        let minimal = games[0].minimal_bag();
+        assert_eq!(minimal.power(&["purple", "red", "orange"]), 6);
+        assert_eq!(minimal.power(&["purple"]), 2);
+        // Purple doesn't make up for the missing green
+        assert_eq!(minimal.power(&PART2_COLOURS), 0);
+        assert!(Day2::parse("Game 1: two red".to_string()).is_err());
+    }
+    #[test]
+    fn missing_colour() {
+        let games = Day2::parse(
+            "Game 1: 3 blue, 4 red; 2 green
+Game 2: 5 red; 2 blue, 1 red"
+                .to_string(),
+        )
+        .unwrap();
+        assert_eq!(games[1].minimal_bag().power(&PART2_COLOURS), 0);
+        assert_eq!(Day2::second(games), 24);
+    }
 }
@@ -1,167 +1,163 @@
 use std::collections::{HashMap, VecDeque};
 
 use super::day::Day;
+use crate::helpers::{
+    graph::{Graph, Interner},
+    number_theory,
+};
 use anyhow::Result;
-use num_integer::Integer;
 
 #[derive(Debug, Clone)]
 pub enum ModuleType {
     Broadcast,
     FlipFlop(bool),
-    Conjunction(HashMap<String, bool>),
+    Conjunction(HashMap<u32, bool>),
+    /// Only ever receives pulses
+    Output,
 }
 
 #[derive(Debug, Clone)]
-pub struct Module {
-    name: String,
-    mt: ModuleType,
-    send_to: Vec<String>,
+pub struct Network {
+    labels: Interner,
+    wires: Graph,
+    modules: Vec<ModuleType>,
+    button: u32,
+    broadcaster: u32,
 }
-impl Module {
-    fn input(
-        &mut self,
-        from: &String,
-        signal: bool,
-        signals: &mut VecDeque<(String, bool, String)>,
-    ) {
-        match self.mt {
-            ModuleType::Broadcast => {
-                for to in &self.send_to {
-                    signals.push_back((to.clone(), signal, self.name.clone()));
-                }
-            }
-            ModuleType::FlipFlop(ref mut mem) => {
-                if !signal {
+impl Network {
+    /// Presses the button once, calling `on_pulse` with `(from, signal, to)` for every pulse.
+    fn press(&mut self, mut on_pulse: impl FnMut(u32, bool, u32)) {
+        let mut signals = VecDeque::from([(self.button, false, self.broadcaster)]);
+        while let Some((from, signal, to)) = signals.pop_front() {
+            on_pulse(from, signal, to);
+            let pulse = match &mut self.modules[to as usize] {
+                ModuleType::Broadcast => Some(signal),
+                ModuleType::FlipFlop(mem) => (!signal).then(|| {
                     *mem = !*mem;
-                    for to in &self.send_to {
-                        signals.push_back((to.clone(), *mem, self.name.clone()));
-                    }
+                    *mem
+                }),
+                ModuleType::Conjunction(mem) => {
+                    *mem.get_mut(&from).unwrap() = signal;
+                    Some(!mem.values().all(|&b| b))
                 }
+                ModuleType::Output => None,
+            };
+            if let Some(pulse) = pulse {
+                signals.extend(self.wires.neighbours(to).map(|next| (to, pulse, next)));
             }
-            ModuleType::Conjunction(ref mut mem) => {
-                *mem.get_mut(from).unwrap() = signal;
-                //dbg!(&mem);
-                let pulse = !mem.iter().all(|(_, b)| *b);
-                for to in &self.send_to {
-                    signals.push_back((to.clone(), pulse, self.name.clone()));
-                }
-            }
-        }
-    }
-    fn has_input(&mut self, input: String) {
-        if let ModuleType::Conjunction(ref mut mem) = self.mt {
-            mem.insert(input, false);
         }
     }
 }
 
 pub struct Day20;
 impl Day for Day20 {
-    type Parsed = HashMap<String, Module>;
+    type Parsed = Network;
     type Output = usize;
 
     fn parse(input: String) -> Result<Self::Parsed> {
-        Ok(input
-            .lines()
-            .map(|line| {
-                let (mut name, send_to) = line.split_once(" -> ").unwrap();
-                let send_to = send_to
-                    .split(", ")
-                    .map(std::string::ToString::to_string)
-                    .collect();
-                let mt = match name.chars().next().unwrap() {
-                    '%' => {
-                        name = &name[1..];
-                        ModuleType::FlipFlop(false)
-                    }
-                    '&' => {
-                        name = &name[1..];
-                        ModuleType::Conjunction(HashMap::new())
-                    }
-                    'b' => ModuleType::Broadcast,
-                    _ => panic!(),
-                };
-                (
-                    name.to_string(),
-                    Module {
-                        name: name.to_string(),
-                        mt,
-                        send_to,
-                    },
-                )
-            })
-            .collect())
-    }
-    fn first(mut modules: Self::Parsed) -> Self::Output {
-        for (name, module) in modules.clone() {
-            for to in module.send_to {
-                if let Some(m) = modules.get_mut(&to) {
-                    m.has_input(name.clone());
+        let mut labels = Interner::new();
+        let mut wires = Graph::new_directed();
+        let mut types = vec![];
+        for line in input.lines() {
+            let (mut name, send_to) = line.split_once(" -> ").unwrap();
+            let mt = match name.chars().next().unwrap() {
+                '%' => {
+                    name = &name[1..];
+                    ModuleType::FlipFlop(false)
+                }
+                '&' => {
+                    name = &name[1..];
+                    ModuleType::Conjunction(HashMap::new())
                 }
+                'b' => ModuleType::Broadcast,
+                _ => panic!(),
+            };
+            let id = labels.intern(name);
+            for to in send_to.split(", ") {
+                wires.add_edge(id, labels.intern(to), ());
+            }
+            types.push((id, mt));
+        }
+        let button = labels.intern("button");
+        let broadcaster = labels.intern("broadcaster");
+        wires.ensure_node(button.max(broadcaster));
+
+        let mut modules = vec![ModuleType::Output; labels.len()];
+        for (id, mt) in types {
+            modules[id as usize] = mt;
+        }
+        for (from, to, ()) in wires.all_edges() {
+            if let ModuleType::Conjunction(mem) = &mut modules[to as usize] {
+                mem.insert(from, false);
             }
         }
+        Ok(Network {
+            labels,
+            wires,
+            modules,
+            button,
+            broadcaster,
+        })
+    }
+    fn first(mut network: Self::Parsed) -> Self::Output {
         let mut low_pulses = 0;
         let mut high_pulses = 0;
         for _ in 0..1000 {
-            let mut signals = VecDeque::new();
-            signals.push_back(("broadcaster".to_string(), false, "button".to_string()));
-            while let Some((to, signal, from)) = signals.pop_front() {
-                if let Some(module) = modules.get_mut(&to) {
-                    module.input(&from, signal, &mut signals);
-                }
+            network.press(|_, signal, _| {
                 if signal {
                     high_pulses += 1;
                 } else {
                     low_pulses += 1;
                 }
-            }
+            });
         }
         low_pulses * high_pulses
     }
-    fn second(mut modules: Self::Parsed) -> Self::Output {
-        for (name, module) in modules.clone() {
-            for to in module.send_to {
-                if let Some(m) = modules.get_mut(&to) {
-                    m.has_input(name.clone());
-                }
-            }
-        }
-
-        let mut end_modules: Vec<HashMap<String, usize>> = modules.iter().filter_map(|(_, module)| {
-            if module.send_to.contains(&"rx".to_string()) {
-                match &module.mt {
-                    ModuleType::Conjunction(mem) => Some(mem.keys().map(|s| (s.to_string(), 0)).collect()),
-                    _ => {
-                        panic!("Day 20 Part 2 checker only supports Conjunction module for end mt sender.")
-                    }
-                }
-            } else {
-                None
-            }
-        }).collect();
-        assert!(end_modules.len() == 1, "Day 20 Part 2 checker only supports one end module.");
-        let mut cycles = end_modules.pop().unwrap();
+    fn second(mut network: Self::Parsed) -> Self::Output {
+        let rx = network.labels.id("rx").expect("No rx module");
+        let inputs = network.wires.reversed();
+        let end_modules: Vec<u32> = inputs.neighbours(rx).collect();
+        assert!(
+            end_modules.len() == 1,
+            "Day 20 Part 2 checker only supports one end module."
+        );
+        assert!(
+            matches!(
+                network.modules[end_modules[0] as usize],
+                ModuleType::Conjunction(_)
+            ),
+            "Day 20 Part 2 checker only supports Conjunction module for end mt sender."
+        );
+        // First two presses each input of the end module fires on
+        let mut cycles: HashMap<u32, Vec<usize>> = inputs
+            .neighbours(end_modules[0])
+            .map(|input| (input, vec![]))
+            .collect();
 
         let mut presses = 0;
         loop {
             presses += 1;
-            let mut signals = VecDeque::new();
-            signals.push_back(("broadcaster".to_string(), false, "button".to_string()));
-            while let Some((to, signal, from)) = signals.pop_front() {
-                if let Some(module) = modules.get_mut(&to) {
-                    module.input(&from, signal, &mut signals);
-                }
+            let mut rx_low = false;
+            network.press(|_, signal, to| {
                 if !signal {
-                    if let Some(cycle) = cycles.get_mut(&to) {
-                        *cycle = presses;
+                    if let Some(hits) = cycles.get_mut(&to) {
+                        if hits.len() < 2 && hits.last() != Some(&presses) {
+                            hits.push(presses);
+                        }
                     }
+                    rx_low |= to == rx;
                 }
-                if !signal && to == "rx" {
-                    return presses;
-                }
+            });
+            if rx_low {
+                return presses;
             }
-            if cycles.values().all(|&cycle| cycle > 0) {
-                return cycles.values().fold(1, |acc, cycle| acc.lcm(cycle));
+            if cycles.values().all(|hits| hits.len() == 2) {
+                let cycles = cycles
+                    .values()
+                    .map(|hits| (hits[0] as i128, (hits[1] - hits[0]) as i128));
+                let presses = number_theory::first_common_hit(cycles)
+                    .expect("End module inputs never line up");
+                return presses.try_into().unwrap();
             }
         }
     }
@@ -180,6 +176,18 @@ mod tests {
 &inv -> b
 %b -> con
 &con -> output";
+    // Counters with all bits set on presses 3, 7, 11.. and 7, 15, 23..
+    const INPUT3: &str = "broadcaster -> a1, b1
+%a1 -> a2, pa
+%a2 -> pa
+&pa -> p
+&p -> z
+%b1 -> b2, qb
+%b2 -> b3, qb
+%b3 -> qb
+&qb -> q
+&q -> z
+&z -> rx";
     fn parsed() -> <Day20 as Day>::Parsed {
         Day20::parse(INPUT.to_string()).unwrap()
     }
@@ -188,7 +196,11 @@ mod tests {
     }
     #[test]
     fn part1() {
-        assert_eq!(Day20::first(parsed()), 32000000);
-        assert_eq!(Day20::first(parsed2()), 11687500);
+        assert_eq!(Day20::first(parsed()), 32_000_000);
+        assert_eq!(Day20::first(parsed2()), 11_687_500);
+    }
+    #[test]
+    fn part2() {
+        assert_eq!(Day20::second(Day20::parse(INPUT3.to_string()).unwrap()), 7);
     }
 }
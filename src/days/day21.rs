@@ -1,10 +1,12 @@
 #![allow(clippy::cast_possible_wrap)]
-#![allow(clippy::cast_precision_loss)]
 #![allow(clippy::cast_possible_truncation)]
-use polyfit_rs::polyfit_rs::polyfit;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
-use crate::helpers::grid2d::{parse_str_grid, Direction4Way, Position2D};
+use crate::helpers::{
+    grid2d::{parse_grid_markers, Direction4Way, GridCell, Position2D, UnknownCell},
+    polynomial::ForwardDifferences,
+    tiled_grid::TiledGrid,
+};
 
 use super::day::Day;
 use anyhow::Result;
@@ -16,6 +18,21 @@ pub enum Ground {
     Garden,
     Rock,
 }
+impl GridCell for Ground {
+    fn try_from_char(c: char) -> Result<Self, UnknownCell> {
+        match c {
+            '.' | 'S' => Ok(Self::Garden),
+            '#' => Ok(Self::Rock),
+            _ => Err(UnknownCell(c)),
+        }
+    }
+    fn to_char(&self) -> char {
+        match self {
+            Self::Garden => '.',
+            Self::Rock => '#',
+        }
+    }
+}
 
 type Pos = Position2D<isize>;
 type Dir = Direction4Way;
@@ -36,14 +53,12 @@ fn print_sparse_grid(grid: &HashSet<Pos>) {
     }
 }
 
-fn visited_gardens(start: Pos, grid: &Array2<Ground>, steps: &[usize]) -> Vec<usize> {
-    let [height, width] = *grid.shape() else {
-        panic!()
-    };
-    let (height, width) = (height as isize, width as isize);
-
+/// Walks the infinite garden, calling `save` with the reachable gardens after each of `steps`.
+fn walk_gardens<F>(start: Pos, tiled: &TiledGrid<Ground>, steps: &[usize], mut save: F)
+where
+    F: FnMut(usize, &HashSet<Pos>),
+{
     let max_steps = *steps.iter().max().unwrap();
-    let mut results = vec![0; steps.len()];
 
     let mut visited: [HashSet<Pos>; 2] = [HashSet::new(), HashSet::new()];
     let mut current: Vec<Pos> = vec![start];
@@ -53,47 +68,46 @@ fn visited_gardens(start: Pos, grid: &Array2<Ground>, steps: &[usize]) -> Vec<us
         for pos in &current {
             for dir in Dir::EVERY {
                 let pos_dir = *pos + dir;
-                let mod_pos = Position2D::new(
-                    pos_dir.x.rem_euclid(width) as usize,
-                    pos_dir.y.rem_euclid(height) as usize,
-                );
-                if !visited[index].contains(&pos_dir) && grid[mod_pos.yx()] == Ground::Garden {
+                if !visited[index].contains(&pos_dir) && *tiled.get(pos_dir) == Ground::Garden {
                     new_pos.push(pos_dir);
                     visited[index].insert(pos_dir);
                 }
             }
         }
         current = new_pos;
-        if let Some(save) = steps.iter().position(|&ns| ns == curr_step + 1) {
-            results[save] = visited[curr_step % 2].len();
+        if let Some(index) = steps.iter().position(|&ns| ns == curr_step + 1) {
+            save(index, &visited[curr_step % 2]);
         }
     }
+}
+
+fn visited_gardens(start: Pos, grid: &Array2<Ground>, steps: &[usize]) -> Vec<usize> {
+    let mut results = vec![0; steps.len()];
+    walk_gardens(start, &TiledGrid::new(grid), steps, |index, visited| {
+        results[index] = visited.len();
+    });
     results
 }
 
+#[allow(dead_code)]
+fn gardens_per_tile(start: Pos, grid: &Array2<Ground>, steps: usize) -> HashMap<Pos, usize> {
+    let tiled = TiledGrid::new(grid);
+    let mut tiles = HashMap::new();
+    walk_gardens(start, &tiled, &[steps], |_, visited| {
+        tiles = tiled.count_per_tile(visited);
+    });
+    tiles
+}
+
 pub struct Day21;
 impl Day for Day21 {
     type Parsed = (Pos, Array2<Ground>);
     type Output = usize;
 
     fn parse(input: String) -> Result<Self::Parsed> {
-        let start = Pos::new_yx(
-            input
-                .lines()
-                .enumerate()
-                .find_map(|(y, line)| {
-                    line.chars()
-                        .position(|c| c == 'S')
-                        .map(|x| (y as isize, x as isize))
-                })
-                .unwrap(),
-        );
-        let grid = parse_str_grid(&input, |c| match c {
-            '.' | 'S' => Ground::Garden,
-            '#' => Ground::Rock,
-            _ => panic!(),
-        })?;
-        Ok((start, grid))
+        let (grid, markers) = parse_grid_markers(&input, &['S'])?;
+        let start = markers.single('S')?;
+        Ok((Pos::new(start.x as isize, start.y as isize), grid))
     }
     fn first((start_pos, grid): Self::Parsed) -> Self::Output {
         visited_gardens(start_pos, &grid, &[64])[0]
@@ -104,29 +118,39 @@ impl Day for Day21 {
         };
         assert!(height == width, "Part 2 only supports square inputs");
 
-        // Estimation of visited gardens based on cycle of input grid size.
-        // This is accurate for aoc input data, but not general.
-
-        let total_steps = 26_501_365;
-        let cycles = total_steps / width;
-        let cycle_start = total_steps % width;
-
-        let steps: Vec<usize> = (0..3).map(|i| cycle_start + i * width).collect();
-        let points: Vec<f64> = visited_gardens(start_pos, &grid, &steps)
-            .into_iter()
-            .map(|v| v as f64)
-            .collect();
-
-        let p_fit: Vec<usize> = polyfit(&[0f64, 1f64, 2f64], &points, 2)
-            .unwrap()
-            .into_iter()
-            .map(|v| v.round() as usize)
-            .collect();
-
-        p_fit[2] * cycles * cycles + p_fit[1] * cycles + p_fit[0]
+        extrapolate_gardens(start_pos, &grid, 26_501_365, 0)
     }
 }
 
+/// Visited gardens after `total_steps`, from how they grow every time the walk crosses another
+/// grid width. Sampling starts after `skip` widths, for grids that take a while to settle.
+/// This is accurate for aoc input data, but not general.
+fn extrapolate_gardens(
+    start: Pos,
+    grid: &Array2<Ground>,
+    total_steps: usize,
+    skip: usize,
+) -> usize {
+    let width = grid.ncols();
+    let first = total_steps % width + skip * width;
+    // Three samples are enough for a quadratic, and the fourth checks the growth is one
+    let steps: Vec<usize> = (0..4).map(|i| first + i * width).collect();
+    let samples: Vec<i128> = visited_gardens(start, grid, &steps)
+        .into_iter()
+        .map(|v| v as i128)
+        .collect();
+
+    let growth = ForwardDifferences::from_samples(&samples);
+    assert!(
+        growth.is_confirmed() && growth.degree() <= Some(2),
+        "Gardens don't grow quadratically"
+    );
+    growth
+        .at(((total_steps - first) / width) as i128)
+        .try_into()
+        .unwrap()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -156,4 +180,19 @@ mod tests {
             [16, 50, 1594, 6536]
         );
     }
+    #[test]
+    fn example_extrapolated() {
+        let (start_pos, grid) = parsed();
+        // The example only settles into a quadratic after a few grid widths
+        assert_eq!(extrapolate_gardens(start_pos, &grid, 1000, 4), 668_697);
+        assert_eq!(extrapolate_gardens(start_pos, &grid, 5000, 4), 16_733_044);
+    }
+    #[test]
+    fn example_tiles() {
+        let (start_pos, grid) = parsed();
+        let tiles = gardens_per_tile(start_pos, &grid, 50);
+        assert_eq!(tiles.values().sum::<usize>(), 1594);
+        assert_eq!(tiles[&Pos::new(0, 0)], 42);
+        assert_eq!(tiles[&Pos::new(-1, 1)], tiles[&Pos::new(1, 1)]);
+    }
 }
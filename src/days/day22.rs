@@ -1,34 +1,17 @@
-use std::{
-    collections::{HashMap, HashSet},
-    mem,
-};
+use std::collections::{HashMap, HashSet};
 
-use crate::helpers::grid2d::Position2D;
+use crate::helpers::{
+    grid2d::Position2D,
+    grid3d::{Box3D, Position3D},
+};
 
 use super::day::Day;
 use anyhow::Result;
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
 
 type Pos2D = Position2D<usize>;
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct Pos3D {
-    x: usize,
-    y: usize,
-    z: usize,
-}
-#[derive(Clone, Copy, Debug)]
-pub struct Brick {
-    left: Pos3D,
-    right: Pos3D,
-}
-impl Brick {
-    fn new(mut left: Pos3D, mut right: Pos3D) -> Self {
-        if right.x < left.x || right.y < left.y || right.z < left.z {
-            mem::swap(&mut left, &mut right);
-        }
-        Self { left, right }
-    }
-}
+type Pos3D = Position3D<usize>;
+type Brick = Box3D<usize>;
 
 struct BrickLayer {
     len: usize,
@@ -38,26 +21,26 @@ struct BrickLayer {
 }
 impl BrickLayer {
     fn compute(mut bricks: Vec<Brick>) -> Self {
-        bricks.sort_unstable_by_key(|brick| brick.left.z);
+        bricks.sort_unstable_by_key(|brick| brick.min.z);
 
         let mut pos_data: HashMap<Pos2D, (usize, usize)> = HashMap::new();
         let mut supported_by: HashMap<usize, HashSet<usize>> = HashMap::new();
         let mut supports: HashMap<usize, HashSet<usize>> = HashMap::new();
 
         for (i, brick) in bricks.iter().enumerate() {
-            let settle_at = (brick.left.x..=brick.right.x)
+            let settle_at = (brick.min.x..=brick.max.x)
                 .map(|x| {
-                    (brick.left.y..=brick.right.y)
+                    (brick.min.y..=brick.max.y)
                         .map(|y| pos_data.get(&Pos2D::new(x, y)).map_or(0, |p| p.0))
                         .max()
                         .unwrap()
                 })
                 .max()
                 .unwrap();
-            let brick_height = brick.right.z - brick.left.z + 1;
+            let brick_height = brick.size().z;
 
-            for x in brick.left.x..=brick.right.x {
-                for y in brick.left.y..=brick.right.y {
+            for x in brick.min.x..=brick.max.x {
+                for y in brick.min.y..=brick.max.y {
                     let data = pos_data
                         .entry(Pos2D::new(x, y))
                         .or_insert_with(|| (0, usize::MAX));
@@ -109,7 +92,7 @@ impl BrickLayer {
             supports
                 .iter()
                 .map(|&support| {
-                    if support_map.get(&support).map_or(true, |&c| c != origin)
+                    if support_map.get(&support).is_none_or(|&c| c != origin)
                         && self.supported_by[&support]
                             .iter()
                             .all(|support| support_map.get(support).is_some_and(|&c| c == origin))
@@ -139,16 +122,8 @@ impl Day for Day22 {
                     .map(|n| n.parse().unwrap())
                     .collect();
                 Brick::new(
-                    Pos3D {
-                        x: nums[0],
-                        y: nums[1],
-                        z: nums[2],
-                    },
-                    Pos3D {
-                        x: nums[3],
-                        y: nums[4],
-                        z: nums[5],
-                    },
+                    Pos3D::new(nums[0], nums[1], nums[2]),
+                    Pos3D::new(nums[3], nums[4], nums[5]),
                 )
             })
             .collect())
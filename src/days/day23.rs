@@ -1,11 +1,12 @@
-use std::collections::{HashMap, HashSet, VecDeque};
-
-use crate::helpers::grid2d::{parse_str_grid, Direction4Way, Position2D};
+use crate::helpers::{
+    grid2d::{parse_grid, Direction4Way, GridCell, Position2D, UnknownCell},
+    junctions::{CellWalk, Corridor, Junctions},
+    longest_path::longest_path,
+};
 
 use super::day::Day;
-use anyhow::Result;
+use anyhow::{Context, Result};
 use ndarray::Array2;
-use smallvec::{smallvec, SmallVec};
 
 type Dir = Direction4Way;
 type Pos = Position2D<usize>;
@@ -16,6 +17,61 @@ pub enum Spot {
     Forest,
     Slope(Dir),
 }
+impl GridCell for Spot {
+    fn try_from_char(c: char) -> Result<Self, UnknownCell> {
+        match c {
+            '.' => Ok(Self::Path),
+            '#' => Ok(Self::Forest),
+            '>' => Ok(Self::Slope(Dir::East)),
+            'v' => Ok(Self::Slope(Dir::South)),
+            '<' => Ok(Self::Slope(Dir::West)),
+            '^' => Ok(Self::Slope(Dir::North)),
+            _ => Err(UnknownCell(c)),
+        }
+    }
+    fn to_char(&self) -> char {
+        match self {
+            Self::Path => '.',
+            Self::Forest => '#',
+            Self::Slope(Dir::East) => '>',
+            Self::Slope(Dir::South) => 'v',
+            Self::Slope(Dir::West) => '<',
+            Self::Slope(Dir::North) => '^',
+        }
+    }
+}
+
+impl Spot {
+    const fn walk(self, slippery: bool) -> CellWalk {
+        match self {
+            Self::Forest => CellWalk::Blocked,
+            Self::Slope(dir) if slippery => CellWalk::OneWay(dir),
+            _ => CellWalk::Open,
+        }
+    }
+}
+
+fn find_path(grid: &Array2<Spot>, y: usize) -> Result<Pos> {
+    grid.row(y)
+        .iter()
+        .position(|spot| *spot == Spot::Path)
+        .map(|x| Pos::new(x, y))
+        .context(format!("No path found on row {y}"))
+}
+
+/// Longest hike from `start` to `end` that never steps on the same cell twice, with every
+/// cell along it.
+fn longest_hike(grid: &Array2<Spot>, start: Pos, end: Pos, slippery: bool) -> (usize, Vec<Pos>) {
+    let junctions = Junctions::compress(grid, |spot| spot.walk(slippery), &[start, end]);
+    let (steps, nodes) = longest_path(
+        junctions.graph(),
+        junctions.id(start).unwrap(),
+        junctions.id(end).unwrap(),
+        Corridor::length,
+    )
+    .expect("No way from start to end");
+    (steps, junctions.expand(&nodes))
+}
 
 pub struct Day23;
 impl Day for Day23 {
@@ -23,143 +79,23 @@ impl Day for Day23 {
     type Output = usize;
 
     fn parse(input: String) -> Result<Self::Parsed> {
-        let grid = parse_str_grid(&input, |c| match c {
-            '.' => Spot::Path,
-            '#' => Spot::Forest,
-            '>' => Spot::Slope(Dir::East),
-            'v' => Spot::Slope(Dir::South),
-            '<' => Spot::Slope(Dir::West),
-            '^' => Spot::Slope(Dir::North),
-            _ => panic!(),
-        })?;
-        let start = Pos::new(input.lines().next().unwrap().find('.').unwrap(), 0);
-        let end = Pos::new(
-            input.lines().next_back().unwrap().find('.').unwrap(),
-            grid.shape()[0] - 1,
-        );
+        let grid: Array2<Spot> = parse_grid(&input)?;
+        let start = find_path(&grid, 0)?;
+        let end = find_path(&grid, grid.nrows().saturating_sub(1))?;
         Ok((grid, start, end))
     }
     fn first((grid, start, end): Self::Parsed) -> Self::Output {
-        let [height, width] = *grid.shape() else {
-            panic!()
-        };
-
-        let mut max_length = 0;
-        let mut queue: VecDeque<(Pos, HashSet<Pos>)> = VecDeque::new();
-        queue.push_back((start, HashSet::new()));
-        while let Some((curr, visited)) = queue.pop_front() {
-            for dir in Dir::EVERY {
-                let next = curr + dir;
-                if next.x < width && next.y < height && !visited.contains(&next) {
-                    if next == end {
-                        max_length = max_length.max(visited.len());
-                    } else {
-                        let spot = grid[next.yx()];
-                        if match spot {
-                            Spot::Path => true,
-                            Spot::Forest => false,
-                            Spot::Slope(slope_dir) => slope_dir == dir,
-                        } {
-                            let mut new_visit = visited.clone();
-                            new_visit.insert(next);
-                            queue.push_back((next, new_visit));
-                        }
-                    }
-                }
-            }
-        }
-        max_length + 1
+        longest_hike(&grid, start, end, true).0
     }
     fn second((grid, start, end): Self::Parsed) -> Self::Output {
-        let [height, width] = *grid.shape() else {
-            panic!()
-        };
-
-        let mut grid_nodes: HashMap<Pos, Node> = HashMap::new();
-
-        for y in 0..height {
-            for x in 0..width {
-                let pos = Pos::new(x, y);
-                if grid.get(pos.yx()).is_some_and(|g| *g == Spot::Forest) {
-                    continue;
-                }
-
-                let ways = get_ways(&grid, pos);
-                if pos == start || pos == end || ways.len() > 2 {
-                    grid_nodes.insert(
-                        pos,
-                        Node {
-                            connections: smallvec![],
-                        },
-                    );
-                    for mut way in ways {
-                        let mut last = pos;
-                        let mut steps = 1;
-                        loop {
-                            let new_ways = get_ways(&grid, way);
-                            if way == start || way == end || new_ways.len() > 2 {
-                                grid_nodes.get_mut(&pos).unwrap().connections.push(Edge {
-                                    to: way,
-                                    length: steps,
-                                });
-                                break;
-                            }
-
-                            steps += 1;
-                            let current_way = way;
-                            way = *new_ways.iter().find(|p| **p != last).unwrap();
-                            last = current_way;
-                        }
-                    }
-                }
-            }
-        }
-
-        let mut max_steps = 0;
-        let mut queue: VecDeque<(Pos, HashSet<Pos>, usize)> = VecDeque::new();
-        queue.push_back((start, HashSet::new(), 0));
-        while let Some((curr, visited, steps)) = queue.pop_front() {
-            for conn in &grid_nodes[&curr].connections {
-                if conn.to == end {
-                    max_steps = max_steps.max(steps + conn.length);
-                } else if !visited.contains(&conn.to) {
-                    let mut new_visit = visited.clone();
-                    new_visit.insert(conn.to);
-                    queue.push_back((conn.to, new_visit, steps + conn.length));
-                }
-            }
-        }
-        max_steps
+        longest_hike(&grid, start, end, false).0
     }
 }
 
-fn get_ways(grid: &Array2<Spot>, pos: Pos) -> SmallVec<[Pos; 4]> {
-    Dir::EVERY
-        .into_iter()
-        .filter_map(|d| {
-            grid.get((pos + d).yx()).and_then(|g| {
-                if *g == Spot::Forest {
-                    None
-                } else {
-                    Some(pos + d)
-                }
-            })
-        })
-        .collect()
-}
-#[derive(Clone, Debug)]
-struct Node {
-    connections: SmallVec<[Edge; 4]>,
-}
-#[derive(Clone, Debug)]
-struct Edge {
-    to: Pos,
-    length: usize,
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::HashSet;
     const INPUT: &str = "#.#####################
 #.......#########...###
 #######.#########.#.###
@@ -194,4 +130,16 @@ mod tests {
     fn part2() {
         assert_eq!(Day23::second(parsed()), 154);
     }
+    #[test]
+    fn hike_path() {
+        let (grid, start, end) = parsed();
+        for slippery in [true, false] {
+            let (steps, path) = longest_hike(&grid, start, end, slippery);
+            assert_eq!(path.len(), steps + 1);
+            assert_eq!((path[0], path[steps]), (start, end));
+            assert!(path.windows(2).all(|pair| pair[0].manhattan(&pair[1]) == 1));
+            assert!(path.iter().all(|pos| grid[pos.yx()] != Spot::Forest));
+            assert_eq!(path.iter().collect::<HashSet<_>>().len(), path.len());
+        }
+    }
 }
@@ -1,27 +1,121 @@
-#![allow(clippy::cast_precision_loss)]
-use crate::helpers::grid2d::Position2D;
+use crate::helpers::{grid2d::Position2D, grid3d::Position3D};
 
 use super::day::Day;
 use anyhow::Result;
 use itertools::Itertools;
+use num_integer::Integer;
 
-type PosI = Position2D<isize>;
-type PosF = Position2D<f64>;
-fn line_intersect_diff(a_start: PosI, a_diff: PosI, b_start: PosI, b_diff: PosI) -> Option<PosF> {
-    let denominator = (b_diff.y * a_diff.x) - (b_diff.x * a_diff.y);
+type PosI = Position2D<i128>;
+
+/// Where two paths cross, as `(x, y, denominator)` with a positive denominator, if both
+/// hailstones get there at or after time 0.
+fn future_crossing(
+    a_start: PosI,
+    a_diff: PosI,
+    b_start: PosI,
+    b_diff: PosI,
+) -> Option<(i128, i128, i128)> {
+    let mut denominator = a_diff.x * b_diff.y - a_diff.y * b_diff.x;
     if denominator == 0 {
         return None;
     }
-    let numerator = (b_diff.x * (a_start.y - b_start.y)) - (b_diff.y * (a_start.x - b_start.x));
-    let slope = numerator as f64 / denominator as f64;
-
-    Some(PosF::new(
-        slope.mul_add(a_diff.x as f64, a_start.x as f64),
-        slope.mul_add(a_diff.y as f64, a_start.y as f64),
+    let start_diff = b_start - a_start;
+    let mut a_time = start_diff.x * b_diff.y - start_diff.y * b_diff.x;
+    let mut b_time = start_diff.x * a_diff.y - start_diff.y * a_diff.x;
+    if denominator < 0 {
+        (denominator, a_time, b_time) = (-denominator, -a_time, -b_time);
+    }
+    if a_time < 0 || b_time < 0 {
+        return None;
+    }
+    Some((
+        a_start.x * denominator + a_time * a_diff.x,
+        a_start.y * denominator + a_time * a_diff.y,
+        denominator,
     ))
 }
 
-type Pos3D = (isize, isize, isize);
+type Pos3D = Position3D<isize>;
+type Pos3I = Position3D<i128>;
+
+/// When something starting at `pos` and moving by `vel` crosses the line through the origin
+/// going along `dir`, if it does so exactly once and at a whole time.
+fn hit_time(pos: Pos3I, vel: Pos3I, dir: Pos3I) -> Option<i128> {
+    let offset = pos.cross(&dir);
+    let speed = vel.cross(&dir);
+    let (offset_k, speed_k) = [
+        (offset.x, speed.x),
+        (offset.y, speed.y),
+        (offset.z, speed.z),
+    ]
+    .into_iter()
+    .find(|&(_, speed)| speed != 0)?;
+    if offset_k % speed_k != 0 {
+        return None;
+    }
+    let time = -offset_k / speed_k;
+    (offset + speed * time == Pos3I::default()).then_some(time)
+}
+
+/// Whether a rock thrown from `rock` hits the hailstone at or after time 0.
+fn collides((rock_pos, rock_vel): (Pos3I, Pos3I), (pos, vel): (Pos3I, Pos3I)) -> bool {
+    let (pos, vel) = (pos - rock_pos, vel - rock_vel);
+    let Some((pos_k, vel_k)) = [(pos.x, vel.x), (pos.y, vel.y), (pos.z, vel.z)]
+        .into_iter()
+        .find(|&(_, vel)| vel != 0)
+    else {
+        return pos == Pos3I::default();
+    };
+    let time = -pos_k / vel_k;
+    pos_k % vel_k == 0 && time >= 0 && pos + vel * time == Pos3I::default()
+}
+
+/// Position and velocity of a rock that hits every hailstone.
+///
+/// Seen from the first hailstone, the rock passes through the origin, so its path lies in
+/// the plane through the origin and any other hailstone's path. Two of those planes meet
+/// along the rock's direction, and where the hailstones cross it gives the collision times.
+fn throw_rock(hail: &[(Pos3D, Pos3D)]) -> Option<(Pos3I, Pos3I)> {
+    let hail: Vec<(Pos3I, Pos3I)> = hail
+        .iter()
+        .map(|(pos, vel)| (pos.map(|n| n as i128), vel.map(|n| n as i128)))
+        .collect();
+    let (&(first_pos, first_vel), rest) = hail.split_first()?;
+    for (&a, &b) in rest.iter().tuple_combinations() {
+        let (a_pos, a_vel) = (a.0 - first_pos, a.1 - first_vel);
+        let (b_pos, b_vel) = (b.0 - first_pos, b.1 - first_vel);
+        let dir = a_pos.cross(&a_vel).cross(&b_pos.cross(&b_vel));
+        let divisor = dir.x.gcd(&dir.y).gcd(&dir.z);
+        if divisor == 0 {
+            continue;
+        }
+        let dir = dir.map(|n| n / divisor);
+        let (Some(a_time), Some(b_time)) =
+            (hit_time(a_pos, a_vel, dir), hit_time(b_pos, b_vel, dir))
+        else {
+            continue;
+        };
+        if a_time == b_time {
+            continue;
+        }
+        let a_hit = a.0 + a.1 * a_time;
+        let b_hit = b.0 + b.1 * b_time;
+        let travelled = b_hit - a_hit;
+        let elapsed = b_time - a_time;
+        if [travelled.x, travelled.y, travelled.z]
+            .iter()
+            .any(|n| n % elapsed != 0)
+        {
+            continue;
+        }
+        let vel = travelled.map(|n| n / elapsed);
+        let rock = (a_hit - vel * a_time, vel);
+        if hail.iter().all(|&stone| collides(rock, stone)) {
+            return Some(rock);
+        }
+    }
+    None
+}
 
 pub struct Day24;
 impl Day for Day24 {
@@ -33,55 +127,41 @@ impl Day for Day24 {
             .lines()
             .map(|line| {
                 let (pos, vel) = line.split_once(" @ ").unwrap();
-                let pos: Pos3D = pos
+                let pos = pos
                     .split(", ")
                     .map(|n| n.trim().parse().unwrap())
                     .collect_tuple()
                     .unwrap();
-                let vel: Pos3D = vel
+                let vel = vel
                     .split(", ")
                     .map(|n| n.trim().parse().unwrap())
                     .collect_tuple()
                     .unwrap();
-                (pos, vel)
+                (Pos3D::new_xyz(pos), Pos3D::new_xyz(vel))
             })
             .collect())
     }
     fn first(hail: Self::Parsed) -> Self::Output {
-        xy_intersections(&hail, 200_000_000_000_000f64, 400_000_000_000_000f64)
+        xy_intersections(&hail, 200_000_000_000_000, 400_000_000_000_000)
     }
-    fn second(_elves: Self::Parsed) -> Self::Output {
-        0
+    fn second(hail: Self::Parsed) -> Self::Output {
+        let (pos, _) = throw_rock(&hail).expect("No rock hits every hailstone");
+        (pos.x + pos.y + pos.z).try_into().unwrap()
     }
 }
 
-fn xy_intersections(hail: &[(Pos3D, Pos3D)], min: f64, max: f64) -> usize {
-    let mut intersections = 0;
-    for (a, b) in hail.iter().tuple_combinations() {
-        let a_start = PosI::new(a.0 .0, a.0 .1);
-        let a_diff = PosI::new(a.1 .0, a.1 .1);
-        let b_start = PosI::new(b.0 .0, b.0 .1);
-        let b_diff = PosI::new(b.1 .0, b.1 .1);
-
-        if let Some(intersect) = line_intersect_diff(a_start, a_diff, b_start, b_diff) {
-            if !(a_diff.x > 0 && intersect.x < a_start.x as f64
-                || a_diff.x < 0 && intersect.x > a_start.x as f64
-                || a_diff.y > 0 && intersect.y < a_start.y as f64
-                || a_diff.y < 0 && intersect.y > a_start.y as f64
-                || b_diff.x > 0 && intersect.x < b_start.x as f64
-                || b_diff.x < 0 && intersect.x > b_start.x as f64
-                || b_diff.y > 0 && intersect.y < b_start.y as f64
-                || b_diff.y < 0 && intersect.y > b_start.y as f64
-                || intersect.x < min
-                || intersect.x > max
-                || intersect.y < min
-                || intersect.y > max)
-            {
-                intersections += 1;
-            }
-        }
-    }
-    intersections
+fn xy_intersections(hail: &[(Pos3D, Pos3D)], min: i128, max: i128) -> usize {
+    let project = |pos: Pos3D| PosI::new(pos.x as i128, pos.y as i128);
+    hail.iter()
+        .tuple_combinations()
+        .filter_map(|(a, b)| {
+            future_crossing(project(a.0), project(a.1), project(b.0), project(b.1))
+        })
+        .filter(|&(x, y, denominator)| {
+            let window = min * denominator..=max * denominator;
+            window.contains(&x) && window.contains(&y)
+        })
+        .count()
 }
 
 #[cfg(test)]
@@ -97,10 +177,74 @@ mod tests {
     }
     #[test]
     fn part1() {
-        assert_eq!(xy_intersections(&parsed(), 7f64, 27f64), 2);
+        assert_eq!(xy_intersections(&parsed(), 7, 27), 2);
+    }
+    #[test]
+    fn window_boundaries() {
+        const MIN: i128 = 200_000_000_000_000;
+        const MAX: i128 = 400_000_000_000_000;
+        let count =
+            |input: &str| xy_intersections(&Day24::parse(input.to_string()).unwrap(), MIN, MAX);
+        // Crossing at x = MAX + 0.001, which f64 rounds down onto the window's edge
+        assert_eq!(
+            count(
+                "399999999999999, 300000000000000, 0 @ 1, 0, 0
+399999999999999, 299999999998999, 0 @ 1, 1000, 0"
+            ),
+            0
+        );
+        // Crossing at x = MIN - 0.001, which f64 rounds up onto the window's edge
+        assert_eq!(
+            count(
+                "199999999999998, 300000000000000, 0 @ 1, 0, 0
+199999999999998, 299999999998001, 0 @ 1, 1000, 0"
+            ),
+            0
+        );
+        // Crossing exactly on the edge counts
+        assert_eq!(
+            count(
+                "399999999999999, 300000000000000, 0 @ 1, 0, 0
+400000000000000, 299999999999000, 0 @ 0, 1000, 0"
+            ),
+            1
+        );
+        // Hailstone B passed the crossing a moment before time 0
+        assert_eq!(
+            count(
+                "299999999999999, 300000000000000, 0 @ 1, 0, 0
+300000000000000, 300000000000001, 0 @ 0, 1000, 0"
+            ),
+            0
+        );
     }
     #[test]
     fn part2() {
-        //assert_eq!(Day24::second(parsed()), 45000);
+        assert_eq!(Day24::second(parsed()), 47);
+        assert_eq!(
+            throw_rock(&parsed()),
+            Some((Pos3I::new(24, 13, 10), Pos3I::new(-3, 1, 2)))
+        );
+    }
+    #[test]
+    fn part2_full_scale() {
+        let input = "423773616808529, 408522801768529, 140955460157849 @ -251, -226, 248
+135033909429564, 400933286925464, 157838342051524 @ 296, -241, 219
+243405917049364, 184719211492669, 184950210005264 @ -212, 144, 128
+331934091533204, 155943317169787, 194134978242959 @ -208, 264, 134
+249208042910108, 155247898035483, 275829989796300 @ -72, 296, -237";
+        let hail = Day24::parse(input.to_string()).unwrap();
+        assert_eq!(
+            throw_rock(&hail),
+            Some((
+                Pos3I::new(
+                    187_016_878_804_004,
+                    175_507_140_888_229,
+                    177_831_791_810_924
+                ),
+                Pos3I::new(192, 210, 179)
+            ))
+        );
+        assert_eq!(Day24::second(hail), 540_355_811_503_157);
     }
 }
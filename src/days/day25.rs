@@ -1,36 +1,116 @@
+use std::collections::VecDeque;
+
 use super::day::Day;
+use crate::helpers::graph::{Graph, Interner};
 use anyhow::Result;
 
+#[derive(Debug, Clone)]
+pub struct Wiring {
+    edges: Vec<(u32, u32)>,
+    /// Every wire, weighted by its index in `edges`
+    graph: Graph<usize>,
+}
+impl Wiring {
+    /// Pushes single units of flow from `source` to `sink` until more than `limit` get
+    /// through. If they can't, returns which components are still reachable from `source`,
+    /// so cutting the saturated wires splits those from the rest.
+    fn min_cut(&self, source: u32, sink: u32, limit: usize) -> Option<Vec<bool>> {
+        // Flow along each edge from its first to its second component, both directions having
+        // a capacity of 1
+        let mut flow = vec![0i32; self.edges.len()];
+        for _ in 0..=limit {
+            let mut came_from: Vec<Option<(u32, usize)>> = vec![None; self.graph.node_count()];
+            let mut reached = vec![false; self.graph.node_count()];
+            reached[source as usize] = true;
+            let mut queue = VecDeque::from([source]);
+            while let Some(from) = queue.pop_front() {
+                if from == sink {
+                    break;
+                }
+                for &(to, edge) in self.graph.edges(from) {
+                    let forward = self.edges[edge].0 == from;
+                    let residual = if forward {
+                        1 - flow[edge]
+                    } else {
+                        1 + flow[edge]
+                    };
+                    if residual > 0 && !reached[to as usize] {
+                        reached[to as usize] = true;
+                        came_from[to as usize] = Some((from, edge));
+                        queue.push_back(to);
+                    }
+                }
+            }
+            if !reached[sink as usize] {
+                return Some(reached);
+            }
+            let mut pos = sink;
+            while let Some((from, edge)) = came_from[pos as usize] {
+                flow[edge] += if self.edges[edge].0 == from { 1 } else { -1 };
+                pos = from;
+            }
+        }
+        None
+    }
+}
+
 pub struct Day25;
 impl Day for Day25 {
-    type Parsed = String;
-    type Output = i32;
+    type Parsed = Wiring;
+    type Output = usize;
 
     fn parse(input: String) -> Result<Self::Parsed> {
-        Ok(input)
+        let mut labels = Interner::new();
+        let mut edges = Vec::new();
+        let mut graph = Graph::new_undirected();
+        for line in input.lines() {
+            let (from, to_list) = line.split_once(": ").unwrap();
+            let from = labels.intern(from);
+            for to in to_list.split_whitespace() {
+                let to = labels.intern(to);
+                graph.add_edge(from, to, edges.len());
+                edges.push((from, to));
+            }
+        }
+        Ok(Wiring { edges, graph })
     }
-    fn first(_elves: Self::Parsed) -> Self::Output {
-        5
+    fn first(wiring: Self::Parsed) -> Self::Output {
+        // Some component ends up on the other side of the cut from component 0
+        let side = wiring
+            .graph
+            .nodes()
+            .skip(1)
+            .find_map(|sink| wiring.min_cut(0, sink, 3))
+            .expect("No three wires split the components in two");
+        let size = side.iter().filter(|&&reached| reached).count();
+        size * (side.len() - size)
     }
-    fn second(_elves: Self::Parsed) -> Self::Output {
-        5
+    fn second(_wiring: Self::Parsed) -> Self::Output {
+        0
     }
 }
 
-/*#[cfg(test)]
+#[cfg(test)]
 mod tests {
     use super::*;
-    const INPUT: &str = "";
+    const INPUT: &str = "jqt: rhn xhk nvd
+rsh: frs pzl lsr
+xhk: hfx
+cmg: qnr nvd lhk bvb
+rhn: xhk bvb hfx
+bvb: xhk hfx
+pzl: lsr hfx nvd
+qnr: nvd
+ntq: jqt hfx bvb xhk
+nvd: lhk
+lsr: lhk
+rzs: qnr cmg lsr rsh
+frs: qnr lhk lsr";
     fn parsed() -> <Day25 as Day>::Parsed {
         Day25::parse(INPUT.to_string()).unwrap()
     }
     #[test]
     fn part1() {
-        assert_eq!(Day25::first(parsed()), 24000);
-    }
-    #[test]
-    fn part2() {
-        assert_eq!(Day25::second(parsed()), 45000);
+        assert_eq!(Day25::first(parsed()), 54);
     }
 }
-*/
\ No newline at end of file
@@ -1,107 +1,168 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, ops::Range};
 
-use crate::helpers::grid2d::{parse_str_grid, Direction8Way, Position2D};
+use crate::helpers::grid2d::{parse_str_grid, Position2D};
 
 use super::day::Day;
 use anyhow::Result;
 use ndarray::Array2;
 
-type Dir = Direction8Way;
 type Pos = Position2D<usize>;
 
-const fn is_symbol(c: char) -> bool {
-    !matches!(c, '0'..='9' | '.')
+/// A number written along a row of the schematic.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Number {
+    pub value: u32,
+    pub row: usize,
+    pub columns: Range<usize>,
 }
-
-pub struct Day3;
-impl Day for Day3 {
-    type Parsed = Array2<char>;
-    type Output = u32;
-
-    fn parse(input: String) -> Result<Self::Parsed> {
-        Ok(parse_str_grid(&input, |c| c)?)
+impl Number {
+    /// Every position touching the number, diagonals included. Can go past the right and
+    /// bottom edges of the grid.
+    fn border(&self) -> impl Iterator<Item = Pos> + '_ {
+        let rows = self.row.saturating_sub(1)..=self.row + 1;
+        rows.flat_map(move |y| {
+            (self.columns.start.saturating_sub(1)..=self.columns.end).map(move |x| Pos::new(x, y))
+        })
     }
-    fn first(input: Self::Parsed) -> Self::Output {
-        let mut result = 0;
-
-        let [height, width] = *input.shape() else {
-            panic!()
-        };
-        for y in 0..height {
-            let mut number = None;
-            let mut touched_symbol = false;
-            for x in 0..width {
-                let c = input[(y, x)];
-                if let '0'..='9' = c {
-                    number = Some(number.unwrap_or(0) * 10 + c.to_digit(10).unwrap());
-                    if !touched_symbol {
-                        let pos = Pos::new(x, y);
-                        for direction in Dir::EVERY {
-                            if input.get((pos + direction).yx()).map(|c| is_symbol(*c))
-                                == Some(true)
-                            {
-                                touched_symbol = true;
-                            }
-                        }
-                    }
-                } else {
-                    if number.is_some() && touched_symbol {
-                        result += number.unwrap();
-                    }
-                    number = None;
-                    touched_symbol = false;
-                }
-            }
-            if number.is_some() && touched_symbol {
-                result += number.unwrap();
-            }
-        }
+}
 
-        result
-    }
-    fn second(input: Self::Parsed) -> Self::Output {
-        let mut star_touchers: HashMap<Pos, Vec<u32>> = HashMap::new();
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Symbol {
+    pub kind: char,
+    pub pos: Pos,
+}
 
-        let [height, width] = *input.shape() else {
-            panic!()
-        };
-        for y in 0..height {
-            let mut number = None;
-            let mut touched_star = None;
-            for x in 0..width {
-                let c = input[(y, x)];
-                if let '0'..='9' = c {
-                    number = Some(number.unwrap_or(0) * 10 + c.to_digit(10).unwrap());
-                    if touched_star.is_none() {
-                        let pos = Pos::new(x, y);
-                        for direction in Dir::EVERY {
-                            if input.get((pos + direction).yx()) == Some(&'*') {
-                                touched_star = Some(pos + direction);
-                            }
-                        }
-                    }
-                } else {
-                    if let (Some(num), Some(star)) = (number, touched_star) {
-                        star_touchers.entry(star).or_default().push(num);
+/// Every number and symbol in the schematic, with which of them touch each other.
+#[derive(Debug, Clone)]
+pub struct Schematic {
+    numbers: Vec<Number>,
+    symbols: Vec<Symbol>,
+    /// Indices of the symbols touching each number
+    number_symbols: Vec<Vec<usize>>,
+    /// Indices of the numbers touching each symbol
+    symbol_numbers: Vec<Vec<usize>>,
+}
+impl Schematic {
+    pub fn new(grid: &Array2<char>) -> Self {
+        let mut numbers = vec![];
+        let mut symbols = vec![];
+        for (y, row) in grid.rows().into_iter().enumerate() {
+            let mut x = 0;
+            while x < row.len() {
+                let c = row[x];
+                if let Some(digit) = c.to_digit(10) {
+                    let start = x;
+                    let mut value = digit;
+                    x += 1;
+                    while let Some(digit) = row.get(x).and_then(|c| c.to_digit(10)) {
+                        value = value * 10 + digit;
+                        x += 1;
                     }
-                    number = None;
-                    touched_star = None;
+                    numbers.push(Number {
+                        value,
+                        row: y,
+                        columns: start..x,
+                    });
+                    continue;
                 }
-            }
-            if let (Some(num), Some(star)) = (number, touched_star) {
-                star_touchers.entry(star).or_default().push(num);
+                if c != '.' {
+                    symbols.push(Symbol {
+                        kind: c,
+                        pos: Pos::new(x, y),
+                    });
+                }
+                x += 1;
             }
         }
 
-        star_touchers
-            .values()
-            .map(|touchers| {
-                if touchers.len() == 2 {
-                    touchers[0] * touchers[1]
-                } else {
-                    0
+        let symbol_ids: HashMap<Pos, usize> = symbols
+            .iter()
+            .enumerate()
+            .map(|(id, symbol)| (symbol.pos, id))
+            .collect();
+        let mut symbol_numbers = vec![vec![]; symbols.len()];
+        let number_symbols: Vec<Vec<usize>> = numbers
+            .iter()
+            .enumerate()
+            .map(|(number, n)| {
+                let touching: Vec<usize> = n
+                    .border()
+                    .filter_map(|pos| symbol_ids.get(&pos).copied())
+                    .collect();
+                for &symbol in &touching {
+                    symbol_numbers[symbol].push(number);
                 }
+                touching
             })
+            .collect();
+        Self {
+            numbers,
+            symbols,
+            number_symbols,
+            symbol_numbers,
+        }
+    }
+    /// Numbers touching at least one symbol.
+    pub fn part_numbers(&self) -> impl Iterator<Item = &Number> {
+        self.numbers
+            .iter()
+            .zip(&self.number_symbols)
+            .filter(|(_, symbols)| !symbols.is_empty())
+            .map(|(number, _)| number)
+    }
+    /// Symbols of this kind touching exactly `count` numbers, with those numbers.
+    pub fn gears(&self, kind: char, count: usize) -> impl Iterator<Item = (&Symbol, Vec<&Number>)> {
+        (0..self.symbols.len())
+            .filter(move |&symbol| {
+                self.symbols[symbol].kind == kind && self.symbol_numbers[symbol].len() == count
+            })
+            .map(|symbol| (&self.symbols[symbol], self.numbers_around(symbol).collect()))
+    }
+}
+
+#[allow(dead_code)]
+impl Schematic {
+    pub fn numbers(&self) -> &[Number] {
+        &self.numbers
+    }
+    pub fn symbols(&self) -> &[Symbol] {
+        &self.symbols
+    }
+    pub fn symbols_around(&self, number: usize) -> impl Iterator<Item = &Symbol> {
+        self.number_symbols[number]
+            .iter()
+            .map(|&symbol| &self.symbols[symbol])
+    }
+    pub fn numbers_around(&self, symbol: usize) -> impl Iterator<Item = &Number> {
+        self.symbol_numbers[symbol]
+            .iter()
+            .map(|&number| &self.numbers[number])
+    }
+    /// Numbers touching any symbol of this kind, each one once.
+    pub fn parts_next_to(&self, kind: char) -> impl Iterator<Item = &Number> {
+        self.numbers
+            .iter()
+            .zip(&self.number_symbols)
+            .filter(move |(_, symbols)| symbols.iter().any(|&s| self.symbols[s].kind == kind))
+            .map(|(number, _)| number)
+    }
+}
+
+pub struct Day3;
+impl Day for Day3 {
+    type Parsed = Schematic;
+    type Output = u32;
+
+    fn parse(input: String) -> Result<Self::Parsed> {
+        Ok(Schematic::new(&parse_str_grid(&input, |c| c)?))
+    }
+    fn first(schematic: Self::Parsed) -> Self::Output {
+        schematic.part_numbers().map(|number| number.value).sum()
+    }
+    fn second(schematic: Self::Parsed) -> Self::Output {
+        schematic
+            .gears('*', 2)
+            .map(|(_, numbers)| numbers.iter().map(|number| number.value).product::<u32>())
             .sum()
     }
 }
@@ -128,6 +189,40 @@ mod tests {
     }
     #[test]
     fn part2() {
-        assert_eq!(Day3::second(parsed()), 467835);
+        assert_eq!(Day3::second(parsed()), 467_835);
+    }
+    #[test]
+    fn adjacency() {
+        let schematic = parsed();
+        assert_eq!(schematic.numbers().len(), 10);
+        assert_eq!(schematic.symbols().len(), 6);
+        assert_eq!(
+            schematic.numbers()[0],
+            Number {
+                value: 467,
+                row: 0,
+                columns: 0..3
+            }
+        );
+        let values = |numbers: Vec<&Number>| numbers.iter().map(|n| n.value).collect::<Vec<_>>();
+        assert_eq!(values(schematic.parts_next_to('$').collect()), [664]);
+        assert_eq!(
+            values(schematic.gears('*', 1).map(|(_, n)| n[0]).collect()),
+            [617]
+        );
+        let dollar = schematic.symbols_around(8).next().unwrap();
+        assert_eq!(dollar.pos, Pos::new(3, 8));
+
+        // One number touching two stars, and a star with three numbers
+        let schematic = Day3::parse("1.2\n*3*\n..4".to_string()).unwrap();
+        let star = |(symbol, numbers): (&Symbol, Vec<&Number>)| (symbol.pos.x, values(numbers));
+        assert_eq!(
+            schematic.gears('*', 3).map(star).collect::<Vec<_>>(),
+            [(2, vec![2, 3, 4])]
+        );
+        assert_eq!(
+            schematic.gears('*', 2).map(star).collect::<Vec<_>>(),
+            [(0, vec![1, 3])]
+        );
     }
 }
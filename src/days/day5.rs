@@ -1,62 +1,140 @@
-use std::ops::Range;
-
-use crate::helpers::RangeIntersect;
+use crate::helpers::{
+    piecewise::{PiecewiseMap, Segment},
+    ranges::MergedRange,
+};
 
 use super::day::Day;
-use anyhow::Result;
-use itertools::Itertools;
-use smallvec::{smallvec, SmallVec};
+use anyhow::{bail, ensure, Context, Result};
 
+/// One map of the almanac, turning numbers of one category into the next.
 #[derive(Debug, Clone)]
-pub struct Map {
-    range: Range<i64>,
-    destination: i64,
+pub struct Stage {
+    pub from: String,
+    pub to: String,
+    pub map: PiecewiseMap<i64>,
 }
+
 #[derive(Debug, Clone)]
 pub struct Almanac {
     seeds: Vec<i64>,
-    maps_list: Vec<Vec<Map>>,
+    stages: Vec<Stage>,
 }
-
-#[derive(Debug, Clone)]
-pub struct Ranger {
-    ranges: SmallVec<[Range<i64>; 32]>,
-}
-impl Ranger {
-    fn new(range: Range<i64>) -> Self {
-        Self {
-            ranges: smallvec![range],
+impl Almanac {
+    /// Checks that the stages form a single chain starting from seeds, with each one going
+    /// from where the one before went to and no category showing up twice.
+    pub fn new(seeds: Vec<i64>, stages: Vec<Stage>) -> Result<Self> {
+        let mut seen = vec!["seed"];
+        for stage in &stages {
+            let previous = seen[seen.len() - 1];
+            ensure!(
+                stage.from == previous,
+                "The {}-to-{} map doesn't follow on from {previous}",
+                stage.from,
+                stage.to
+            );
+            ensure!(
+                !seen.contains(&stage.to.as_str()),
+                "The {}-to-{} map goes back to an earlier category",
+                stage.from,
+                stage.to
+            );
+            seen.push(&stage.to);
+        }
+        Ok(Self { seeds, stages })
+    }
+    /// Every category in order, starting with seed.
+    pub fn categories(&self) -> impl Iterator<Item = &str> {
+        std::iter::once("seed").chain(self.stages.iter().map(|stage| stage.to.as_str()))
+    }
+    fn position(&self, category: &str) -> Result<usize> {
+        self.categories()
+            .position(|c| c == category)
+            .with_context(|| format!("No {category} category in the almanac"))
+    }
+    /// Map going from `from` numbers straight to `to` numbers. When `to` comes first this
+    /// inverts the maps going the other way, which only works if no two numbers lead to the
+    /// same one.
+    pub fn map_between(&self, from: &str, to: &str) -> Result<PiecewiseMap<i64>> {
+        let (start, end) = (self.position(from)?, self.position(to)?);
+        if start > end {
+            return self.map_between(to, from)?.invert().with_context(|| {
+                format!("Some {from} numbers come from several {to} numbers")
+            });
         }
+        Ok(PiecewiseMap::compose(
+            self.stages[start..end].iter().map(|stage| &stage.map),
+        ))
     }
-    fn apply_maps(&mut self, maps: &Vec<Map>) {
-        let mut finished_ranges: SmallVec<[_; 32]> = SmallVec::new();
-        for map in maps {
-            let mut outside_ranges: SmallVec<[_; 32]> = SmallVec::new();
-            while let Some(range) = self.ranges.pop() {
-                // Is the range to the "left" of the map
-                if range.start < map.range.start {
-                    // Add part of the range that is to the "left" of the map
-                    outside_ranges.push(range.start..range.end.min(map.range.start));
-                }
-                // Does the range intersect with the map
-                if let Some(intersect) = map.range.intersect(&range) {
-                    // The interecting range is changed according to the map
-                    let diff = map.destination - map.range.start;
-                    finished_ranges.push(intersect.start + diff..intersect.end + diff);
-                }
-                // Is the range to the "right" of the map
-                if range.end > map.range.end {
-                    // Add part of the range that is to the "right" of the map
-                    outside_ranges.push(map.range.end.max(range.start)..range.end);
-                }
-            }
-            self.ranges = outside_ranges;
+    /// The `to` number for a `from` number.
+    #[allow(dead_code)]
+    pub fn lookup(&self, from: &str, to: &str, value: i64) -> Result<i64> {
+        Ok(self.map_between(from, to)?.apply(value))
+    }
+    /// Every `from` number leading to one of the `to` numbers in `values`.
+    #[allow(dead_code)]
+    pub fn sources(
+        &self,
+        from: &str,
+        to: &str,
+        values: &MergedRange<i64>,
+    ) -> Result<MergedRange<i64>> {
+        if self.position(from)? > self.position(to)? {
+            // Going backwards the sources are where the values lead, even if that isn't
+            // reversible
+            return Ok(self.map_between(to, from)?.apply_set(values));
         }
-        self.ranges.append(&mut finished_ranges);
+        Ok(self.map_between(from, to)?.preimage(values))
     }
-    fn min(&self) -> i64 {
-        self.ranges.iter().map(|r| r.start).min().unwrap()
+    /// The seed's number in every category, in order.
+    #[allow(dead_code)]
+    pub fn trace(&self, seed: i64) -> Vec<(&str, i64)> {
+        let numbers = self.stages.iter().scan(seed, |value, stage| {
+            *value = stage.map.apply(*value);
+            Some(*value)
+        });
+        self.categories()
+            .zip(std::iter::once(seed).chain(numbers))
+            .collect()
     }
+    /// Seeds when the seeds line lists pairs of starts and lengths.
+    pub fn seed_ranges(&self) -> MergedRange<i64> {
+        self.seeds
+            .chunks_exact(2)
+            .map(|chunk| chunk[0]..chunk[0] + chunk[1])
+            .collect()
+    }
+    /// Lowest location any of `seeds` lead to, with all the seeds leading there.
+    pub fn lowest_location(&self, seeds: &MergedRange<i64>) -> Option<(i64, MergedRange<i64>)> {
+        let map = self.map_between("seed", self.categories().last()?).ok()?;
+        let lowest = map.apply_set(seeds).min()?;
+        let lowest_seeds = map
+            .preimage(&std::iter::once(lowest..lowest + 1).collect())
+            .intersection(seeds);
+        Some((lowest, lowest_seeds))
+    }
+}
+
+fn parse_stage(section: &str) -> Result<Stage> {
+    let (header, numbers) = section.split_once('\n').unwrap_or((section, ""));
+    let (from, to) = header
+        .strip_suffix(" map:")
+        .and_then(|name| name.split_once("-to-"))
+        .with_context(|| format!("Invalid map name: {header}"))?;
+    let segments: Vec<Segment<i64>> = numbers
+        .lines()
+        .map(|line| {
+            let numbers: Vec<i64> = line.split(' ').map(str::parse).collect::<Result<_, _>>()?;
+            let [destination, source, len] = numbers[..] else {
+                bail!("Expected three numbers: {line}");
+            };
+            Ok(Segment::to_destination(source..source + len, destination))
+        })
+        .collect::<Result<_>>()?;
+    Ok(Stage {
+        from: from.to_string(),
+        to: to.to_string(),
+        map: PiecewiseMap::from_segments(segments),
+    })
 }
 
 pub struct Day5;
@@ -65,59 +143,22 @@ impl Day for Day5 {
     type Output = i64;
 
     fn parse(input: String) -> Result<Self::Parsed> {
-        let (seeds, maps) = input.split_once("\n\n").unwrap();
-        let seeds = seeds.split(' ').filter_map(|s| s.parse().ok()).collect();
-        let maps_list = maps
-            .split("\n\n")
-            .map(|map| {
-                map.lines()
-                    .skip(1)
-                    .filter_map(|line| {
-                        let nums: (i64, i64, i64) = line
-                            .split(' ')
-                            .map(|s| s.parse::<i64>().unwrap())
-                            .collect_tuple()?;
-                        Some(Map {
-                            range: nums.1..(nums.1 + nums.2),
-                            destination: nums.0,
-                        })
-                    })
-                    .collect()
-            })
-            .collect();
-        Ok(Almanac { seeds, maps_list })
+        let (seeds, maps) = input.split_once("\n\n").context("No maps")?;
+        let seeds = seeds
+            .strip_prefix("seeds:")
+            .context("No seeds")?
+            .split_whitespace()
+            .map(str::parse)
+            .collect::<Result<_, _>>()?;
+        let stages = maps.split("\n\n").map(parse_stage).collect::<Result<_>>()?;
+        Almanac::new(seeds, stages)
     }
     fn first(almanac: Self::Parsed) -> Self::Output {
-        almanac
-            .seeds
-            .into_iter()
-            .map(|mut seed| {
-                for maps in &almanac.maps_list {
-                    for map in maps {
-                        if map.range.contains(&seed) {
-                            seed += map.destination - map.range.start;
-                            break;
-                        }
-                    }
-                }
-                seed
-            })
-            .min()
-            .unwrap()
+        let seeds = almanac.seeds.iter().map(|&seed| seed..seed + 1).collect();
+        almanac.lowest_location(&seeds).unwrap().0
     }
     fn second(almanac: Self::Parsed) -> Self::Output {
-        almanac
-            .seeds
-            .chunks_exact(2)
-            .map(|chunk| {
-                let mut ranger = Ranger::new(chunk[0]..chunk[0] + chunk[1]);
-                for maps in &almanac.maps_list {
-                    ranger.apply_maps(maps);
-                }
-                ranger.min()
-            })
-            .min()
-            .unwrap()
+        almanac.lowest_location(&almanac.seed_ranges()).unwrap().0
     }
 }
 
@@ -168,4 +209,94 @@ humidity-to-location map:
     fn part2() {
         assert_eq!(Day5::second(parsed()), 46);
     }
+    #[test]
+    fn reverse_lookup() {
+        let almanac = parsed();
+        let location_to_seed = almanac
+            .map_between("seed", "location")
+            .unwrap()
+            .invert()
+            .unwrap();
+        assert_eq!(location_to_seed.apply(46), 82);
+        assert_eq!(location_to_seed.apply(35), 13);
+        let single = |n: i64| std::iter::once(n..n + 1).collect::<MergedRange<i64>>();
+        assert_eq!(
+            almanac.sources("seed", "location", &single(46)).unwrap(),
+            single(82)
+        );
+        assert_eq!(
+            almanac.sources("soil", "water", &single(81)).unwrap(),
+            single(81)
+        );
+        assert_eq!(almanac.lookup("seed", "fertilizer", 79).unwrap(), 81);
+        assert!(almanac.lookup("seed", "moon", 81).is_err());
+    }
+    #[test]
+    fn backwards() {
+        let almanac = parsed();
+        assert_eq!(almanac.lookup("water", "seed", 81).unwrap(), 79);
+        assert_eq!(almanac.lookup("location", "seed", 46).unwrap(), 82);
+        assert_eq!(almanac.lookup("light", "light", 5).unwrap(), 5);
+        let single = |n: i64| std::iter::once(n..n + 1).collect::<MergedRange<i64>>();
+        assert_eq!(
+            almanac.sources("water", "seed", &single(79)).unwrap(),
+            single(81)
+        );
+
+        // Soil 2 comes from seeds 2 and 12, so soil numbers can't be turned back into seeds
+        let merging = Day5::parse("seeds: 1\n\nseed-to-soil map:\n0 10 5".to_string()).unwrap();
+        assert_eq!(
+            merging.lookup("soil", "seed", 2).unwrap_err().to_string(),
+            "Some soil numbers come from several seed numbers"
+        );
+        assert_eq!(
+            merging.sources("seed", "soil", &single(2)).unwrap(),
+            [2..3, 12..13].into_iter().collect()
+        );
+        // but the soil numbers some seeds lead to can still be found
+        assert_eq!(
+            merging
+                .sources("soil", "seed", &[2..3, 12..13].into_iter().collect())
+                .unwrap(),
+            single(2)
+        );
+    }
+    #[test]
+    fn trace() {
+        let almanac = parsed();
+        assert_eq!(
+            almanac.trace(79),
+            [
+                ("seed", 79),
+                ("soil", 81),
+                ("fertilizer", 81),
+                ("water", 81),
+                ("light", 74),
+                ("temperature", 78),
+                ("humidity", 78),
+                ("location", 82)
+            ]
+        );
+        let (lowest, seeds) = almanac.lowest_location(&almanac.seed_ranges()).unwrap();
+        assert_eq!(lowest, 46);
+        assert_eq!(seeds, std::iter::once(82..83).collect());
+        // Seeds can reach the same location along different paths through the maps
+        let everything = std::iter::once(0..100).collect();
+        let (lowest, seeds) = almanac.lowest_location(&everything).unwrap();
+        assert_eq!(lowest, 0);
+        assert!(seeds
+            .iter()
+            .all(|range| almanac.trace(range.start)[7].1 == 0));
+    }
+    #[test]
+    fn broken_chain() {
+        let skipped = INPUT.replace("soil-to-fertilizer", "soil-to-water");
+        let error = Day5::parse(skipped).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "The fertilizer-to-water map doesn't follow on from water"
+        );
+        let looped = INPUT.replace("humidity-to-location", "humidity-to-soil");
+        assert!(Day5::parse(looped).is_err());
+    }
 }
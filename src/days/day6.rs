@@ -1,70 +1,88 @@
 use super::day::Day;
-use anyhow::Result;
-use itertools::Itertools;
-use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use anyhow::{Context, Result};
 
 #[derive(Debug, Clone)]
 pub struct Race {
-    time: i64,
-    distance: i64,
+    time: u128,
+    distance: u128,
+}
+impl Race {
+    /// How many whole hold times beat the record, counted without trying them.
+    ///
+    /// With `time = 2h + e`, holding `h - k` or `h + e + k` goes `h(h + e) - k(k + e)`, so the
+    /// winning holds are the `k` with `k(k + e) < h(h + e) - distance`. Works for any time
+    /// below 2^65, where the longest possible distance still fits in a `u128`.
+    pub fn winning_holds(&self) -> u128 {
+        let half = self.time / 2;
+        let odd = self.time % 2;
+        let best = half
+            .checked_mul(half + odd)
+            .expect("Race too long to solve");
+        let Some(margin) = best.checked_sub(self.distance).filter(|&m| m > 0) else {
+            return 0;
+        };
+        // Largest k with k(k + e) < margin, starting from the square root and nudging it
+        let mut k = margin.isqrt();
+        while k * (k + odd) >= margin {
+            k -= 1;
+        }
+        while (k + 1) * (k + 1 + odd) < margin {
+            k += 1;
+        }
+        if odd == 0 {
+            2 * k + 1
+        } else {
+            2 * (k + 1)
+        }
+    }
 }
 
 pub struct Day6;
 impl Day for Day6 {
     type Parsed = (Vec<Race>, Race);
-    type Output = usize;
+    type Output = u128;
 
     fn parse(input: String) -> Result<Self::Parsed> {
-        let (times, distances) = input.lines().collect_tuple().unwrap();
-        let times = times.split_once(':').unwrap().1;
-        let distances = distances.split_once(':').unwrap().1;
+        let mut lines = input.lines().map(|line| {
+            line.split_once(':')
+                .map(|(_, numbers)| numbers)
+                .context("Missing ':'")
+        });
+        let (Some(times), Some(distances)) = (lines.next(), lines.next()) else {
+            anyhow::bail!("Expected a time and a distance line");
+        };
+        let (times, distances) = (times?, distances?);
 
-        let first = times
-            .split_whitespace()
-            .filter_map(|n| n.parse::<i64>().ok())
-            .zip(
-                distances
-                    .split_whitespace()
-                    .filter_map(|n| n.parse::<i64>().ok()),
-            )
+        let numbers = |line: &str| {
+            line.split_whitespace()
+                .map(str::parse)
+                .collect::<Result<Vec<u128>, _>>()
+        };
+        let first = numbers(times)?
+            .into_iter()
+            .zip(numbers(distances)?)
             .map(|(time, distance)| Race { time, distance })
             .collect();
 
         let second = Race {
-            time: times.replace(' ', "").parse::<i64>().unwrap(),
-            distance: distances.replace(' ', "").parse::<i64>().unwrap(),
+            time: times.replace(' ', "").parse()?,
+            distance: distances.replace(' ', "").parse()?,
         };
 
         Ok((first, second))
     }
     fn first((races, _): Self::Parsed) -> Self::Output {
-        races
-            .iter()
-            .map(|race| {
-                (1..race.time)
-                    .map(|speed| {
-                        let time_left = race.time - speed;
-                        time_left * speed
-                    })
-                    .filter(|distance| distance > &race.distance)
-                    .count()
-            })
-            .product()
+        races.iter().map(Race::winning_holds).product()
     }
     fn second((_, race): Self::Parsed) -> Self::Output {
-        (1..race.time).into_par_iter()
-            .map(|speed| {
-                let time_left = race.time - speed;
-                time_left * speed
-            })
-            .filter(|distance| distance > &race.distance)
-            .count()
+        race.winning_holds()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::helpers::test_rng::XorShift;
     const INPUT: &str = "Time:      7  15   30
 Distance:  9  40  200";
     fn parsed() -> <Day6 as Day>::Parsed {
@@ -78,4 +96,40 @@ Distance:  9  40  200";
     fn part2() {
         assert_eq!(Day6::second(parsed()), 71503);
     }
+
+    fn brute_force(race: &Race) -> u128 {
+        (0..=race.time)
+            .filter(|&hold| hold * (race.time - hold) > race.distance)
+            .count() as u128
+    }
+    #[test]
+    fn against_brute_force() {
+        let mut rng = XorShift::new(6);
+        for _ in 0..2_000 {
+            let time = u128::from(rng.range(0..300));
+            let best = (time / 2) * (time - time / 2);
+            // Mostly records near the best distance, where the edges are tightest
+            let distance = best.saturating_sub(u128::from(rng.range(0..50)));
+            let race = Race { time, distance };
+            assert_eq!(race.winning_holds(), brute_force(&race), "{race:?}");
+            let race = Race {
+                time,
+                distance: u128::from(rng.range(0..u64::try_from(best).unwrap() + 2)),
+            };
+            assert_eq!(race.winning_holds(), brute_force(&race), "{race:?}");
+        }
+    }
+    #[test]
+    fn huge_races() {
+        let race = |time: u128, distance: u128| Race { time, distance }.winning_holds();
+        let time = 1 << 64;
+        let best = 1 << 126;
+        assert_eq!(race(time, best), 0);
+        assert_eq!(race(time, best - 1), 1);
+        assert_eq!(race(time + 1, best + (1 << 63) - 1), 2);
+        // Holding exactly 2^32 less than half way is 2^64 short of the best
+        assert_eq!(race(time, best - (1 << 64)), 2 * ((1 << 32) - 1) + 1);
+        let max_time = u128::MAX >> 63;
+        assert_eq!(race(max_time, 0), max_time - 1);
+    }
 }
@@ -1,115 +1,213 @@
-#![allow(clippy::cast_lossless)]
-
 use super::day::Day;
-use anyhow::Result;
+use anyhow::{ensure, Context, Result};
 use itertools::Itertools;
 
-fn card_to_u32(input: char) -> u32 {
-    match input {
-        'A' => 14,
-        'K' => 13,
-        'Q' => 12,
-        'J' => 11,
-        'T' => 10,
-        _ => input.to_digit(10).unwrap(),
+/// Hand categories from weakest to strongest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum HandType {
+    HighCard,
+    OnePair,
+    TwoPair,
+    ThreeOfAKind,
+    FullHouse,
+    FourOfAKind,
+    FiveOfAKind,
+}
+impl HandType {
+    const ALL: [Self; 7] = [
+        Self::HighCard,
+        Self::OnePair,
+        Self::TwoPair,
+        Self::ThreeOfAKind,
+        Self::FullHouse,
+        Self::FourOfAKind,
+        Self::FiveOfAKind,
+    ];
+    /// Sizes of the groups of equal cards a hand needs for this category, largest first.
+    const fn groups(self) -> &'static [usize] {
+        match self {
+            Self::HighCard => &[],
+            Self::OnePair => &[2],
+            Self::TwoPair => &[2, 2],
+            Self::ThreeOfAKind => &[3],
+            Self::FullHouse => &[3, 2],
+            Self::FourOfAKind => &[4],
+            Self::FiveOfAKind => &[5],
+        }
     }
 }
+
+/// How hands of the same category are ordered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TieBreak {
+    /// Compare the cards one by one in the order they were dealt
+    Dealt,
+    /// Compare the strongest cards first, like poker kickers
+    Strongest,
+}
+
+/// Rules for comparing hands of Camel Cards.
 #[derive(Debug, Clone)]
-pub struct Hand([u32; 5]);
-impl Hand {
-    fn hand_type(&self) -> u64 {
-        let counts = self.0.into_iter().counts();
-        match counts.len() {
-            1 => 7,
-            2 => match *counts.values().next().unwrap() {
-                1 | 4 => 6,
-                _ => 5,
-            },
-            3 => match counts.values().max().unwrap() {
-                3 => 4,
-                _ => 3,
-            },
-            4 => 2,
-            _ => 1,
+pub struct Ruleset {
+    /// Every card from weakest to strongest
+    order: Vec<char>,
+    /// Cards that can stand in for any other card to make the best category
+    wildcards: Vec<char>,
+    /// Whether wildcards lose every tie instead of keeping their place in `order`
+    wildcards_weakest: bool,
+    tie_break: TieBreak,
+    /// Group sizes each category needs, largest first, from the weakest category to the
+    /// strongest
+    categories: Vec<Vec<usize>>,
+}
+impl Ruleset {
+    /// Rules with the usual categories from high card to five of a kind.
+    pub fn new(order: &str, wildcards: &str, wildcards_weakest: bool, tie_break: TieBreak) -> Self {
+        Self {
+            order: order.chars().collect(),
+            wildcards: wildcards.chars().collect(),
+            wildcards_weakest,
+            tie_break,
+            categories: HandType::ALL
+                .iter()
+                .map(|kind| kind.groups().to_vec())
+                .collect(),
         }
     }
-    const fn card_score(&self) -> u64 {
-        ((self.0[0] as u64) << 16)
-            + ((self.0[1] as u64) << 12)
-            + ((self.0[2] as u64) << 8)
-            + ((self.0[3] as u64) << 4)
-            + (self.0[4] as u64)
-    }
-    fn score(&self) -> u64 {
-        (self.hand_type() << 20) + self.card_score()
-    }
-    fn replace_j_with(&self, replace: u32) -> Self {
-        Self(self.0.map(|card| if card == 11 { replace } else { card }))
-    }
-    fn j_score(&self) -> u64 {
-        let counts = self.0.into_iter().counts();
-        match counts.get(&11).unwrap_or(&0) {
-            0 => self.score(),
-            5 => self.replace_j_with(1).score(),
-            _ => {
-                counts.iter().filter(|(num, _)| **num != 11).map(|(to_be_copied, _)| {
-                    let hand = self.replace_j_with(*to_be_copied);
-                    let score_hand = self.replace_j_with(1);
-                    (hand.hand_type() << 20) + score_hand.card_score()
-                }).max().unwrap()
-            }
+    /// Replaces the categories, given from weakest to strongest as the sizes of the groups of
+    /// equal cards each one needs. Hands matching none of them rank below all of them.
+    #[allow(dead_code)]
+    pub fn with_categories<'a>(
+        mut self,
+        categories: impl IntoIterator<Item = &'a [usize]>,
+    ) -> Self {
+        self.categories = categories
+            .into_iter()
+            .map(|groups| {
+                groups
+                    .iter()
+                    .copied()
+                    .sorted_unstable_by(|a, b| b.cmp(a))
+                    .collect()
+            })
+            .collect();
+        self
+    }
+    /// The rules from part 1.
+    pub fn standard() -> Self {
+        Self::new("23456789TJQKA", "", false, TieBreak::Dealt)
+    }
+    /// The rules from part 2, where jokers are wild but weakest.
+    pub fn jokers() -> Self {
+        Self::new("23456789TJQKA", "J", true, TieBreak::Dealt)
+    }
+    fn strength(&self, card: char) -> Option<usize> {
+        let position = self.order.iter().position(|&c| c == card)?;
+        Some(
+            if self.wildcards_weakest && self.wildcards.contains(&card) {
+                0
+            } else {
+                position + 1
+            },
+        )
+    }
+    /// Index of the strongest category the cards can make. A category is in reach when the
+    /// wildcards can make up what the groups lack, matching the largest groups it needs with
+    /// the largest groups of other cards.
+    pub fn category(&self, cards: &[char]) -> Option<usize> {
+        let (wild, fixed): (Vec<char>, Vec<char>) =
+            cards.iter().partition(|card| self.wildcards.contains(card));
+        let mut groups: Vec<usize> = fixed.into_iter().counts().into_values().collect();
+        groups.sort_unstable_by(|a, b| b.cmp(a));
+        self.categories.iter().rposition(|needed| {
+            let missing: usize = needed
+                .iter()
+                .zip(groups.iter().chain(std::iter::repeat(&0)))
+                .map(|(need, have)| need.saturating_sub(*have))
+                .sum();
+            missing <= wild.len()
+        })
+    }
+    pub fn hand(&self, cards: &str) -> Result<Hand> {
+        let cards: Vec<char> = cards.chars().collect();
+        let mut strengths = cards
+            .iter()
+            .map(|&card| {
+                self.strength(card)
+                    .with_context(|| format!("Unknown card {card}"))
+            })
+            .collect::<Result<Vec<usize>>>()?;
+        if self.tie_break == TieBreak::Strongest {
+            strengths.sort_unstable_by(|a, b| b.cmp(a));
         }
+        Ok(Hand {
+            category: self.category(&cards),
+            strengths,
+        })
     }
 }
 
+/// Hand scored under a ruleset, ordered by category and then card strengths. Only compare
+/// hands made with the same one.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Hand {
+    category: Option<usize>,
+    strengths: Vec<usize>,
+}
+
+/// Every hand scored under `rules`, with its bid.
+fn score(bids: &[(String, u64)], rules: &Ruleset) -> Result<Vec<(Hand, u64)>> {
+    bids.iter()
+        .map(|(cards, bid)| {
+            let hand = rules
+                .hand(cards)
+                .with_context(|| format!("Invalid hand {cards}"))?;
+            Ok((hand, *bid))
+        })
+        .collect()
+}
+
+/// Sum of every bid multiplied by the rank of its hand, weakest first.
+fn winnings(hands: &[(Hand, u64)]) -> u64 {
+    hands
+        .iter()
+        .sorted_unstable()
+        .zip(1..)
+        .map(|((_, bid), rank)| rank * bid)
+        .sum()
+}
+
+/// The bids with their hands scored under the rules of each part.
+#[derive(Debug, Clone)]
+pub struct Hands {
+    standard: Vec<(Hand, u64)>,
+    jokers: Vec<(Hand, u64)>,
+}
+
 pub struct Day7;
 impl Day for Day7 {
-    type Parsed = Vec<(Hand, u32)>;
+    type Parsed = Hands;
     type Output = u64;
 
     fn parse(input: String) -> Result<Self::Parsed> {
-        Ok(input
+        let bids: Vec<(String, u64)> = input
             .lines()
             .map(|line| {
-                let (hand, bid) = line.split_once(' ').unwrap();
-                (
-                    Hand(
-                        hand.chars()
-                            .map(card_to_u32)
-                            .collect::<Vec<u32>>()
-                            .try_into()
-                            .unwrap(),
-                    ),
-                    bid.parse().unwrap(),
-                )
+                let (cards, bid) = line.split_once(' ').context("Missing bid")?;
+                ensure!(cards.chars().count() == 5, "Hand needs five cards: {cards}");
+                Ok((cards.to_string(), bid.parse()?))
             })
-            .collect())
+            .collect::<Result<_>>()?;
+        Ok(Hands {
+            standard: score(&bids, &Ruleset::standard())?,
+            jokers: score(&bids, &Ruleset::jokers())?,
+        })
     }
     fn first(hands: Self::Parsed) -> Self::Output {
-        let mut hands: Vec<(u64, u32)> = hands
-            .into_iter()
-            .map(|(hand, bid)| (hand.score(), bid))
-            .collect();
-        hands.sort_unstable_by_key(|(hand, _)| *hand);
-
-        hands
-            .iter()
-            .enumerate()
-            .map(|(i, (_, bid))| (i + 1) as u64 * *bid as u64)
-            .sum()
+        winnings(&hands.standard)
     }
     fn second(hands: Self::Parsed) -> Self::Output {
-        let mut hands: Vec<(u64, u32)> = hands
-            .into_iter()
-            .map(|(hand, bid)| (hand.j_score(), bid))
-            .collect();
-        hands.sort_unstable_by_key(|(hand, _)| *hand);
-
-        hands
-            .iter()
-            .enumerate()
-            .map(|(i, (_, bid))| (i + 1) as u64 * *bid as u64)
-            .sum()
+        winnings(&hands.jokers)
     }
 }
 
@@ -156,4 +254,45 @@ JJJJ2 41";
         assert_eq!(Day7::second(parsed()), 5905);
         assert_eq!(Day7::second(parsed2()), 6839);
     }
+    #[test]
+    fn wildcards() {
+        let jokers = Ruleset::jokers();
+        let kind = |rules: &Ruleset, cards: &str| rules.hand(cards).unwrap().category;
+        let standard = |kind: HandType| Some(kind as usize);
+        assert_eq!(kind(&jokers, "JJJJJ"), standard(HandType::FiveOfAKind));
+        assert_eq!(kind(&jokers, "J2J3J"), standard(HandType::FourOfAKind));
+        assert_eq!(kind(&jokers, "2233J"), standard(HandType::FullHouse));
+        assert_eq!(kind(&jokers, "2345J"), standard(HandType::OnePair));
+        // Five jokers are the weakest five of a kind
+        assert!(jokers.hand("JJJJJ").unwrap() < jokers.hand("22222").unwrap());
+        assert!(jokers.hand("JKKK2").unwrap() < jokers.hand("QQQQ2").unwrap());
+
+        let two_wild = Ruleset::new("23456789TJQKA", "J2", false, TieBreak::Dealt);
+        assert_eq!(kind(&two_wild, "J2JA2"), standard(HandType::FiveOfAKind));
+        assert_eq!(kind(&two_wild, "2J345"), standard(HandType::ThreeOfAKind));
+        assert_eq!(kind(&two_wild, "22JJJ"), standard(HandType::FiveOfAKind));
+        // Wildcards keep their own strength for ties here
+        assert!(two_wild.hand("JJAAA").unwrap() > two_wild.hand("TTAAA").unwrap());
+
+        // With two pair above three of a kind the joker has to make the second pair
+        let pairs = Ruleset::jokers().with_categories([&[2][..], &[3], &[2, 2]]);
+        assert_eq!(kind(&pairs, "2344J"), Some(2));
+        assert_eq!(kind(&pairs, "2344Q"), Some(0));
+        assert_eq!(kind(&pairs, "2345Q"), None);
+        assert!(pairs.hand("2345Q").unwrap() < pairs.hand("2344Q").unwrap());
+
+        let kickers = Ruleset::new("23456789TJQKA", "", false, TieBreak::Strongest);
+        assert!(kickers.hand("23AA4").unwrap() > kickers.hand("K3KQ2").unwrap());
+        assert_eq!(
+            kickers.hand("2AA43").unwrap().strengths,
+            kickers.hand("AA234").unwrap().strengths
+        );
+        let dealt = Ruleset::standard();
+        assert!(dealt.hand("23AA4").unwrap() < dealt.hand("K3KQ2").unwrap());
+        assert!(Ruleset::standard().hand("2345X").is_err());
+        // The jokers only count as weakest under the rules that make them wild
+        assert!(dealt.hand("2345J").unwrap() > dealt.hand("2345T").unwrap());
+        assert!(jokers.hand("2345J").unwrap() > jokers.hand("2345T").unwrap());
+        assert!(jokers.hand("J2345").unwrap() < jokers.hand("22345").unwrap());
+    }
 }
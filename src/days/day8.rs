@@ -1,17 +1,86 @@
-use std::collections::HashMap;
-
-use crate::helpers::grid2d::Turn;
+use crate::helpers::{
+    cycle::{self, Cycle},
+    graph::{Graph, Interner},
+    grid2d::Turn,
+    number_theory,
+};
 
 use super::day::Day;
 use anyhow::Result;
-use num_integer::Integer;
-use rayon::iter::{IntoParallelRefMutIterator, ParallelIterator};
+use itertools::Itertools;
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
 
 #[derive(Debug, Clone)]
 pub struct Input {
     path: Vec<Turn>,
-    nodes: HashMap<String, (String, String)>
+    labels: Interner,
+    /// Every node has an edge for each way it can turn
+    network: Graph<Turn>,
+}
+impl Input {
+    fn step(&self, node: u32, turn: Turn) -> u32 {
+        self.network
+            .edges(node)
+            .iter()
+            .find(|&&(_, edge_turn)| edge_turn == turn)
+            .unwrap()
+            .0
+    }
+    fn ends_with(&self, node: u32, c: char) -> bool {
+        self.labels.label(node).ends_with(c)
+    }
+    /// Where a ghost starting at `start` goes, tracking which instruction it's on since the
+    /// same node can lead somewhere else further along the path.
+    fn ghost_walk(&self, start: u32, is_end: impl Fn(u32) -> bool) -> GhostWalk {
+        let (cycle, states) = cycle::find_cycle_with_states(&(start, 0), |&(node, i)| {
+            (self.step(node, self.path[i]), (i + 1) % self.path.len())
+        });
+        let hits = (0..states.len())
+            .filter(|&step| is_end(states[step].0))
+            .collect();
+        GhostWalk { cycle, hits }
+    }
 }
+
+/// Steps where a ghost is on an end node, which repeat forever after its first pass through
+/// a loop.
+#[derive(Debug, Clone)]
+pub struct GhostWalk {
+    cycle: Cycle,
+    /// Every hit up to the end of the first pass through the loop, in order
+    hits: Vec<usize>,
+}
+impl GhostWalk {
+    fn hits_at(&self, step: usize) -> bool {
+        self.hits.binary_search(&self.cycle.reduce(step)).is_ok()
+    }
+    /// Hits that come back every loop, as `(offset, period)`.
+    fn repeating_hits(&self) -> Vec<(i128, i128)> {
+        self.hits
+            .iter()
+            .filter(|&&step| step >= self.cycle.start)
+            .map(|&step| (step as i128, self.cycle.length as i128))
+            .collect()
+    }
+}
+
+/// First step where every ghost is on an end node at once, if there ever is one.
+fn first_meeting(walks: &[GhostWalk]) -> Option<usize> {
+    // Until every ghost is looping, just check each step
+    let looping = walks.iter().map(|walk| walk.cycle.start).max()?;
+    if let Some(step) = (0..looping).find(|&step| walks.iter().all(|walk| walk.hits_at(step))) {
+        return Some(step);
+    }
+    // After that, ghosts can pass several end nodes per loop, so try every combination
+    walks
+        .iter()
+        .map(GhostWalk::repeating_hits)
+        .multi_cartesian_product()
+        .filter_map(number_theory::first_common_hit)
+        .min()
+        .map(|step| step.try_into().unwrap())
+}
+
 pub struct Day8;
 impl Day for Day8 {
     type Parsed = Input;
@@ -24,54 +93,38 @@ impl Day for Day8 {
             .map(|c| if c == 'R' { Turn::Right } else { Turn::Left })
             .collect();
 
-        let mut nodes: HashMap<String, (String, String)> = HashMap::new();
-
+        let mut labels = Interner::new();
+        let mut network = Graph::new_directed();
         for line in node_list.lines() {
             let (node, paths) = line.split_once(" = ").unwrap();
             let (left, right) = paths.split_once(", ").unwrap();
-            nodes.insert(
-                node.to_string(),
-                (left[1..4].to_string(), right[0..3].to_string()),
-            );
+            let node = labels.intern(node);
+            network.add_edge(node, labels.intern(&left[1..4]), Turn::Left);
+            network.add_edge(node, labels.intern(&right[0..3]), Turn::Right);
         }
 
-        Ok(Input { path, nodes })
+        Ok(Input {
+            path,
+            labels,
+            network,
+        })
     }
     fn first(input: Self::Parsed) -> Self::Output {
-        let mut position = "AAA".to_string();
-        for (steps, turn) in input.path.iter().cycle().enumerate() {
-            if position == "ZZZ" {
-                return steps;
-            }
-            if *turn == Turn::Left {
-                position = input.nodes.get(&position).unwrap().0.clone();
-            } else {
-                position = input.nodes.get(&position).unwrap().1.clone();
-            }
-        }
-        unreachable!()
+        let start = input.labels.id("AAA").unwrap();
+        let end = input.labels.id("ZZZ").unwrap();
+        let walk = input.ghost_walk(start, |node| node == end);
+        first_meeting(&[walk]).expect("Never reaches ZZZ")
     }
     fn second(input: Self::Parsed) -> Self::Output {
-        let mut positions: Vec<(String, usize)> = input.nodes
-            .keys()
-            .filter(|node| node.ends_with('A'))
-            .cloned()
-            .map(|s| (s, 0))
+        let walks: Vec<GhostWalk> = input
+            .network
+            .nodes()
+            .filter(|&node| input.ends_with(node, 'A'))
+            .collect::<Vec<u32>>()
+            .par_iter()
+            .map(|&start| input.ghost_walk(start, |node| input.ends_with(node, 'Z')))
             .collect();
-        positions.par_iter_mut().for_each(|(position, steps)| {
-            for turn in input.path.iter().cycle() {
-                if position.ends_with('Z') {
-                    break;
-                }
-                *steps += 1;
-                if *turn == Turn::Left {
-                    *position = input.nodes.get(position).unwrap().0.clone();
-                } else {
-                    *position = input.nodes.get(position).unwrap().1.clone();
-                }
-            }
-        });
-        positions.iter().fold(1, |acc, (_, steps)| acc.lcm(steps))
+        first_meeting(&walks).expect("Ghosts are never all on Z nodes at once")
     }
 }
 
@@ -111,9 +164,22 @@ XXX = (XXX, XXX)";
     fn parsed2() -> <Day8 as Day>::Parsed {
         Day8::parse(INPUT2.to_string()).unwrap()
     }
+    const INPUT4: &str = "L
+
+11A = (11Z, 11Z)
+11Z = (11B, 11B)
+11B = (11C, 11C)
+11C = (11Z, 11Z)
+22A = (22B, 22B)
+22B = (22Z, 22Z)
+22Z = (22B, 22B)";
+
     fn parsed3() -> <Day8 as Day>::Parsed {
         Day8::parse(INPUT3.to_string()).unwrap()
     }
+    fn parsed4() -> <Day8 as Day>::Parsed {
+        Day8::parse(INPUT4.to_string()).unwrap()
+    }
 
     #[test]
     fn part1() {
@@ -124,4 +190,40 @@ XXX = (XXX, XXX)";
     fn part2() {
         assert_eq!(Day8::second(parsed3()), 6);
     }
+    #[test]
+    fn part2_offsets() {
+        // 11A hits Z at 1, 4, 7.. and 22A at 2, 4, 6..
+        assert_eq!(Day8::second(parsed4()), 4);
+    }
+    #[test]
+    fn part2_prefix() {
+        // 11A passes 11Z once at step 2 before getting stuck on 11C, while 22A stays on 22Z
+        // from step 2 onwards
+        let input = Day8::parse(
+            "L
+
+11A = (11B, 11B)
+11B = (11Z, 11Z)
+11Z = (11C, 11C)
+11C = (11C, 11C)
+22A = (22B, 22B)
+22B = (22Z, 22Z)
+22Z = (22Z, 22Z)"
+                .to_string(),
+        )
+        .unwrap();
+        assert_eq!(Day8::second(input), 2);
+        let never =
+            Day8::parse(INPUT4.replace("22Z = (22B, 22B)", "22Z = (22X, 22X)\n22X = (22B, 22B)"))
+                .unwrap();
+        let walks: Vec<GhostWalk> = ["11A", "22A"]
+            .iter()
+            .map(|start| {
+                never.ghost_walk(never.labels.id(start).unwrap(), |node| {
+                    never.ends_with(node, 'Z')
+                })
+            })
+            .collect();
+        assert_eq!(first_meeting(&walks), None);
+    }
 }
@@ -1,18 +1,15 @@
-use crate::helpers::BorrowTwo;
+use crate::helpers::polynomial::ForwardDifferences;
 
 use super::day::Day;
 use anyhow::Result;
 
-fn predict_future(mut history: Vec<i64>) -> i64 {
-    let mut sum = 0;
-    while !history.iter().all(|&i| i == 0) {
-        for i in 0..(history.len()-1) {
-            let (left, right) = history.borrow_two(i, i+1);
-            *left = *right - *left;
-        }
-        sum += history.pop().unwrap();
-    }
-    sum
+/// Value of the sequence continued to `index`, which can be past either end of `history`.
+fn extrapolate(history: &[i64], index: i128) -> i64 {
+    let samples: Vec<i128> = history.iter().map(|&v| i128::from(v)).collect();
+    ForwardDifferences::from_samples(&samples)
+        .at(index)
+        .try_into()
+        .unwrap()
 }
 
 pub struct Day9;
@@ -28,17 +25,14 @@ impl Day for Day9 {
     }
     fn first(histories: Self::Parsed) -> Self::Output {
         histories
-            .into_iter()
-            .map(predict_future)
+            .iter()
+            .map(|history| extrapolate(history, history.len() as i128))
             .sum()
     }
     fn second(histories: Self::Parsed) -> Self::Output {
         histories
-            .into_iter()
-            .map(|mut history| {
-                history.reverse();
-                predict_future(history)
-            })
+            .iter()
+            .map(|history| extrapolate(history, -1))
             .sum()
     }
 }
@@ -60,4 +54,10 @@ mod tests {
     fn part2() {
         assert_eq!(Day9::second(parsed()), 2);
     }
+    #[test]
+    fn far_away() {
+        let history = &parsed()[1];
+        assert_eq!(extrapolate(history, 99), 5050);
+        assert_eq!(extrapolate(history, -100), 4851);
+    }
 }
@@ -0,0 +1,198 @@
+#![allow(dead_code)]
+use ndarray::Array2;
+
+use super::{grid2d::Position2D, LongBitArr};
+
+type Pos = Position2D<usize>;
+
+/// Grid of booleans stored as one `LongBitArr` per row, with bit `x` being column `x`.
+///
+/// Whole-grid shifts and set operations work a row at a time, which makes
+/// cellular steps (like expanding a BFS frontier by one) bit-parallel.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BitGrid<const SIZE: usize> {
+    rows: Vec<LongBitArr<SIZE>>,
+    width: usize,
+    mask: LongBitArr<SIZE>,
+}
+impl<const SIZE: usize> BitGrid<SIZE> {
+    pub fn new(width: usize, height: usize) -> Self {
+        assert!(
+            width <= LongBitArr::<SIZE>::BITS,
+            "Width {width} doesn't fit in BitGrid<{SIZE}>"
+        );
+        Self {
+            rows: vec![LongBitArr::new(); height],
+            width,
+            mask: LongBitArr::with_first(width),
+        }
+    }
+    pub fn from_grid<T, F>(grid: &Array2<T>, mut predicate: F) -> Self
+    where
+        F: FnMut(&T) -> bool,
+    {
+        let [height, width] = *grid.shape() else {
+            panic!()
+        };
+        let mut new = Self::new(width, height);
+        for ((y, x), cell) in grid.indexed_iter() {
+            if predicate(cell) {
+                new.rows[y].set(x);
+            }
+        }
+        new
+    }
+    pub const fn width(&self) -> usize {
+        self.width
+    }
+    pub const fn height(&self) -> usize {
+        self.rows.len()
+    }
+    pub fn get(&self, pos: Pos) -> bool {
+        pos.x < self.width && pos.y < self.rows.len() && self.rows[pos.y].get(pos.x)
+    }
+    pub fn set(&mut self, pos: Pos) {
+        assert!(pos.x < self.width);
+        self.rows[pos.y].set(pos.x);
+    }
+    pub fn clear(&mut self, pos: Pos) {
+        assert!(pos.x < self.width);
+        self.rows[pos.y].clear(pos.x);
+    }
+    pub fn insert(&mut self, pos: Pos) -> bool {
+        assert!(pos.x < self.width);
+        self.rows[pos.y].insert(pos.x)
+    }
+    pub fn row(&self, y: usize) -> &LongBitArr<SIZE> {
+        &self.rows[y]
+    }
+    pub fn row_mut(&mut self, y: usize) -> &mut LongBitArr<SIZE> {
+        &mut self.rows[y]
+    }
+    pub fn count_ones(&self) -> usize {
+        self.rows.iter().map(LongBitArr::count_ones).sum()
+    }
+    pub fn iter(&self) -> impl Iterator<Item = Pos> + '_ {
+        self.rows
+            .iter()
+            .enumerate()
+            .flat_map(|(y, row)| row.iter().map(move |x| Pos::new(x, y)))
+    }
+
+    fn map_rows(&self, mut f: impl FnMut(usize, &LongBitArr<SIZE>) -> LongBitArr<SIZE>) -> Self {
+        Self {
+            rows: self
+                .rows
+                .iter()
+                .enumerate()
+                .map(|(y, row)| f(y, row) & self.mask)
+                .collect(),
+            width: self.width,
+            mask: self.mask,
+        }
+    }
+    fn zip_rows(
+        &self,
+        other: &Self,
+        f: impl Fn(LongBitArr<SIZE>, LongBitArr<SIZE>) -> LongBitArr<SIZE>,
+    ) -> Self {
+        assert!(self.width == other.width && self.height() == other.height());
+        self.map_rows(|y, row| f(*row, other.rows[y]))
+    }
+    pub fn union(&self, other: &Self) -> Self {
+        self.zip_rows(other, |a, b| a | b)
+    }
+    pub fn intersection(&self, other: &Self) -> Self {
+        self.zip_rows(other, |a, b| a & b)
+    }
+    pub fn difference(&self, other: &Self) -> Self {
+        self.zip_rows(other, |a, b| a & !b)
+    }
+    pub fn invert(&self) -> Self {
+        self.map_rows(|_, row| !*row)
+    }
+
+    /// Every set cell moved one step east (`x + 1`). Cells leaving the grid are dropped.
+    pub fn shift_east(&self) -> Self {
+        self.map_rows(|_, row| *row << 1)
+    }
+    /// Every set cell moved one step west (`x - 1`).
+    pub fn shift_west(&self) -> Self {
+        self.map_rows(|_, row| *row >> 1)
+    }
+    /// Every set cell moved one step south (`y + 1`).
+    pub fn shift_south(&self) -> Self {
+        self.map_rows(|y, _| {
+            y.checked_sub(1)
+                .map_or_else(LongBitArr::new, |above| self.rows[above])
+        })
+    }
+    /// Every set cell moved one step north (`y - 1`).
+    pub fn shift_north(&self) -> Self {
+        self.map_rows(|y, _| self.rows.get(y + 1).copied().unwrap_or_default())
+    }
+    /// Cells in `open` that are a 4-way neighbour of any set cell.
+    pub fn step_4way(&self, open: &Self) -> Self {
+        self.map_rows(|y, row| {
+            let mut next = (*row << 1) | (*row >> 1);
+            if let Some(above) = y.checked_sub(1) {
+                next |= self.rows[above];
+            }
+            if let Some(below) = self.rows.get(y + 1) {
+                next |= *below;
+            }
+            next & open.rows[y]
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::grid2d::parse_str_grid;
+    const INPUT: &str = "...........
+.....###.#.
+.###.##..#.
+..#.#...#..
+....#.#....
+.##..S####.
+.##..#...#.
+.......##..
+.##.#.####.
+.##..##.##.
+...........";
+    #[test]
+    fn garden_steps() {
+        let grid = parse_str_grid(INPUT, |c| c).unwrap();
+        let open = BitGrid::<1>::from_grid(&grid, |&c| c != '#');
+        let mut reached = BitGrid::<1>::from_grid(&grid, |&c| c == 'S');
+        for _ in 0..6 {
+            reached = reached.step_4way(&open);
+        }
+        assert_eq!(reached.count_ones(), 16);
+        assert!(reached.iter().all(|pos| open.get(pos)));
+    }
+    #[test]
+    fn shifts() {
+        let mut grid = BitGrid::<1>::new(3, 2);
+        grid.set(Pos::new(2, 0));
+        grid.set(Pos::new(0, 1));
+        assert_eq!(
+            grid.shift_east().iter().collect::<Vec<_>>(),
+            [Pos::new(1, 1)]
+        );
+        assert_eq!(
+            grid.shift_west().iter().collect::<Vec<_>>(),
+            [Pos::new(1, 0)]
+        );
+        assert_eq!(
+            grid.shift_south().iter().collect::<Vec<_>>(),
+            [Pos::new(2, 1)]
+        );
+        assert_eq!(
+            grid.shift_north().iter().collect::<Vec<_>>(),
+            [Pos::new(0, 0)]
+        );
+        assert_eq!(grid.invert().count_ones(), 4);
+    }
+}
@@ -0,0 +1,163 @@
+use std::{
+    collections::HashMap,
+    hash::{BuildHasher, Hash, RandomState},
+};
+
+use smallvec::SmallVec;
+
+/// A sequence of states that repeats forever once it reaches `start`.
+///
+/// State `start + length` is equal to state `start`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cycle {
+    pub start: usize,
+    pub length: usize,
+}
+impl Cycle {
+    /// The lowest step with the same state as `step`.
+    pub const fn reduce(&self, step: usize) -> usize {
+        if step < self.start {
+            step
+        } else {
+            self.start + (step - self.start) % self.length
+        }
+    }
+}
+
+/// Finds the cycle by remembering every state seen. Returns the states up to the repeat.
+pub fn find_cycle_with_states<S, F>(initial: &S, mut step: F) -> (Cycle, Vec<S>)
+where
+    S: Clone + Hash + Eq,
+    F: FnMut(&S) -> S,
+{
+    // States are only kept in `states`, with the map going from hashes to their indices
+    let hasher = RandomState::new();
+    let mut seen: HashMap<u64, SmallVec<[usize; 1]>> = HashMap::new();
+    let mut states = vec![initial.clone()];
+    seen.entry(hasher.hash_one(initial)).or_default().push(0);
+    loop {
+        let next = step(states.last().unwrap());
+        let indices = seen.entry(hasher.hash_one(&next)).or_default();
+        if let Some(&start) = indices.iter().find(|&&i| states[i] == next) {
+            let cycle = Cycle {
+                start,
+                length: states.len() - start,
+            };
+            return (cycle, states);
+        }
+        indices.push(states.len());
+        states.push(next);
+    }
+}
+
+pub fn find_cycle<S, F>(initial: &S, step: F) -> Cycle
+where
+    S: Clone + Hash + Eq,
+    F: FnMut(&S) -> S,
+{
+    find_cycle_with_states(initial, step).0
+}
+
+/// Finds the cycle with Brent's algorithm, only keeping two states in memory at once.
+pub fn find_cycle_brent<S, F>(initial: &S, mut step: F) -> Cycle
+where
+    S: Clone + Eq,
+    F: FnMut(&S) -> S,
+{
+    let mut power = 1;
+    let mut length = 1;
+    let mut tortoise = initial.clone();
+    let mut hare = step(initial);
+    while tortoise != hare {
+        if power == length {
+            tortoise = hare.clone();
+            power *= 2;
+            length = 0;
+        }
+        hare = step(&hare);
+        length += 1;
+    }
+
+    let mut tortoise = initial.clone();
+    let mut hare = initial.clone();
+    for _ in 0..length {
+        hare = step(&hare);
+    }
+    let mut start = 0;
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        start += 1;
+    }
+    Cycle { start, length }
+}
+
+/// The state after `n` steps, skipping over repeats of the cycle.
+pub fn state_at<S, F>(initial: &S, step: F, n: usize) -> S
+where
+    S: Clone + Hash + Eq,
+    F: FnMut(&S) -> S,
+{
+    let (cycle, mut states) = find_cycle_with_states(initial, step);
+    states.swap_remove(cycle.reduce(n))
+}
+
+/// `project` of the state after `n` steps, without storing any states along the way.
+pub fn project_at<S, F, P, V>(initial: &S, mut step: F, project: P, n: usize) -> V
+where
+    S: Clone + Eq,
+    F: FnMut(&S) -> S,
+    P: FnOnce(&S) -> V,
+{
+    let cycle = find_cycle_brent(initial, &mut step);
+    let state = (0..cycle.reduce(n)).fold(initial.clone(), |state, _| step(&state));
+    project(&state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    fn next(x: u64) -> u64 {
+        (x * x + 7) % 1009
+    }
+    #[allow(clippy::trivially_copy_pass_by_ref)]
+    fn step(x: &u64) -> u64 {
+        next(*x)
+    }
+    #[test]
+    fn strategies_agree() {
+        for initial in 0..200 {
+            let mut naive = vec![initial];
+            while !naive[..naive.len() - 1].contains(naive.last().unwrap()) {
+                naive.push(next(*naive.last().unwrap()));
+            }
+            let last = *naive.last().unwrap();
+            let start = naive.iter().position(|&x| x == last).unwrap();
+            let expected = Cycle {
+                start,
+                length: naive.len() - 1 - start,
+            };
+            assert_eq!(find_cycle(&initial, step), expected);
+            assert_eq!(find_cycle_brent(&initial, step), expected);
+
+            for n in [0, 5, 77, 1_000_000_007] {
+                let reduced = (0..expected.reduce(n)).fold(initial, |x, _| next(x));
+                assert_eq!(state_at(&initial, step, n), reduced);
+                assert_eq!(project_at(&initial, step, |x| x % 10, n), reduced % 10);
+            }
+        }
+    }
+    #[test]
+    fn hash_collisions() {
+        /// Hashes every state the same, so they can only be told apart by comparing them
+        #[derive(Clone, PartialEq, Eq)]
+        struct Colliding(u64);
+        impl Hash for Colliding {
+            fn hash<H: std::hash::Hasher>(&self, _: &mut H) {}
+        }
+        let (cycle, states) = find_cycle_with_states(&Colliding(3), |x| Colliding(next(x.0)));
+        assert_eq!(cycle, find_cycle(&3, step));
+        assert_eq!(states.len(), cycle.start + cycle.length);
+        assert_eq!(states[cycle.start].0, next(states.last().unwrap().0));
+    }
+}
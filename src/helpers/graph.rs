@@ -0,0 +1,392 @@
+#![allow(dead_code)]
+#![allow(clippy::cast_possible_truncation)]
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::Write,
+};
+
+/// Gives every label a dense id, in the order they're first seen.
+#[derive(Debug, Clone, Default)]
+pub struct Interner {
+    ids: HashMap<String, u32>,
+    labels: Vec<String>,
+}
+impl Interner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn intern(&mut self, label: &str) -> u32 {
+        if let Some(&id) = self.ids.get(label) {
+            return id;
+        }
+        let id = u32::try_from(self.labels.len()).expect("Too many labels");
+        self.ids.insert(label.to_string(), id);
+        self.labels.push(label.to_string());
+        id
+    }
+    pub fn id(&self, label: &str) -> Option<u32> {
+        self.ids.get(label).copied()
+    }
+    pub fn label(&self, id: u32) -> &str {
+        &self.labels[id as usize]
+    }
+    pub fn labels(&self) -> &[String] {
+        &self.labels
+    }
+    pub const fn len(&self) -> usize {
+        self.labels.len()
+    }
+    pub const fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+    }
+}
+
+/// Adjacency lists over dense node ids, with a weight of type `E` on every edge.
+///
+/// Undirected edges are stored once in each direction, in the order they were added, apart
+/// from self-loops which are only stored once.
+#[derive(Debug, Clone)]
+pub struct Graph<E = ()> {
+    directed: bool,
+    adjacent: Vec<Vec<(u32, E)>>,
+    edge_count: usize,
+}
+impl<E: Clone> Graph<E> {
+    pub const fn new_directed() -> Self {
+        Self {
+            directed: true,
+            adjacent: vec![],
+            edge_count: 0,
+        }
+    }
+    pub const fn new_undirected() -> Self {
+        Self {
+            directed: false,
+            adjacent: vec![],
+            edge_count: 0,
+        }
+    }
+    pub fn add_node(&mut self) -> u32 {
+        self.adjacent.push(vec![]);
+        u32::try_from(self.adjacent.len() - 1).expect("Too many nodes")
+    }
+    /// Makes sure ids up to and including `node` exist.
+    pub fn ensure_node(&mut self, node: u32) {
+        if self.adjacent.len() <= node as usize {
+            self.adjacent.resize(node as usize + 1, vec![]);
+        }
+    }
+    pub fn add_edge(&mut self, from: u32, to: u32, weight: E) {
+        self.ensure_node(from.max(to));
+        if !self.directed && from != to {
+            self.adjacent[to as usize].push((from, weight.clone()));
+        }
+        self.adjacent[from as usize].push((to, weight));
+        self.edge_count += 1;
+    }
+    /// Graph with every edge turned around.
+    pub fn reversed(&self) -> Self {
+        if !self.directed {
+            return self.clone();
+        }
+        let mut reversed = Self::new_directed();
+        reversed.adjacent = vec![vec![]; self.node_count()];
+        for (from, to, weight) in self.all_edges() {
+            reversed.add_edge(to, from, weight.clone());
+        }
+        reversed
+    }
+}
+impl<E> Graph<E> {
+    pub const fn is_directed(&self) -> bool {
+        self.directed
+    }
+    pub const fn node_count(&self) -> usize {
+        self.adjacent.len()
+    }
+    pub const fn edge_count(&self) -> usize {
+        self.edge_count
+    }
+    pub fn nodes(&self) -> impl Iterator<Item = u32> {
+        0..self.adjacent.len() as u32
+    }
+    pub fn edges(&self, node: u32) -> &[(u32, E)] {
+        &self.adjacent[node as usize]
+    }
+    pub fn neighbours(&self, node: u32) -> impl Iterator<Item = u32> + '_ {
+        self.adjacent[node as usize].iter().map(|&(to, _)| to)
+    }
+    /// Every edge as `(from, to, weight)`, undirected edges only once.
+    pub fn all_edges(&self) -> impl Iterator<Item = (u32, u32, &E)> {
+        let directed = self.directed;
+        self.nodes().flat_map(move |from| {
+            self.edges(from)
+                .iter()
+                .filter(move |&&(to, _)| directed || from <= to)
+                .map(move |(to, weight)| (from, *to, weight))
+        })
+    }
+
+    /// Shortest number of edges from `start` to every node, if it can be reached.
+    pub fn distances(&self, start: u32) -> Vec<Option<usize>> {
+        let mut distances = vec![None; self.node_count()];
+        distances[start as usize] = Some(0);
+        let mut queue = VecDeque::from([start]);
+        while let Some(node) = queue.pop_front() {
+            let distance = distances[node as usize].unwrap();
+            for next in self.neighbours(node) {
+                if distances[next as usize].is_none() {
+                    distances[next as usize] = Some(distance + 1);
+                    queue.push_back(next);
+                }
+            }
+        }
+        distances
+    }
+    /// Nodes reachable from `start` in breadth-first order.
+    pub fn bfs(&self, start: u32) -> Vec<u32> {
+        let mut seen = vec![false; self.node_count()];
+        seen[start as usize] = true;
+        let mut order = vec![start];
+        let mut i = 0;
+        while let Some(&node) = order.get(i) {
+            for next in self.neighbours(node) {
+                if !seen[next as usize] {
+                    seen[next as usize] = true;
+                    order.push(next);
+                }
+            }
+            i += 1;
+        }
+        order
+    }
+    /// Nodes reachable from `start` in depth-first pre-order, following edges in the order
+    /// they were added.
+    pub fn dfs(&self, start: u32) -> Vec<u32> {
+        let mut seen = vec![false; self.node_count()];
+        let mut order = vec![];
+        let mut stack = vec![start];
+        while let Some(node) = stack.pop() {
+            if seen[node as usize] {
+                continue;
+            }
+            seen[node as usize] = true;
+            order.push(node);
+            stack.extend(
+                self.neighbours(node)
+                    .filter(|&next| !seen[next as usize])
+                    .collect::<Vec<_>>()
+                    .into_iter()
+                    .rev(),
+            );
+        }
+        order
+    }
+    /// Groups of nodes connected to each other, ignoring edge direction.
+    pub fn components(&self) -> Vec<Vec<u32>> {
+        let mut undirected: Vec<Vec<u32>> = vec![vec![]; self.node_count()];
+        for (from, to, _) in self.all_edges() {
+            undirected[from as usize].push(to);
+            undirected[to as usize].push(from);
+        }
+        let mut seen = vec![false; self.node_count()];
+        let mut components = vec![];
+        for start in self.nodes() {
+            if seen[start as usize] {
+                continue;
+            }
+            seen[start as usize] = true;
+            let mut component = vec![start];
+            let mut i = 0;
+            while let Some(&node) = component.get(i) {
+                for &next in &undirected[node as usize] {
+                    if !seen[next as usize] {
+                        seen[next as usize] = true;
+                        component.push(next);
+                    }
+                }
+                i += 1;
+            }
+            components.push(component);
+        }
+        components
+    }
+    /// Order where every edge goes forward, or `None` if the graph has a cycle.
+    pub fn toposort(&self) -> Option<Vec<u32>> {
+        let mut incoming = vec![0usize; self.node_count()];
+        for (_, to, _) in self.all_edges() {
+            incoming[to as usize] += 1;
+        }
+        let mut order: Vec<u32> = self
+            .nodes()
+            .filter(|&node| incoming[node as usize] == 0)
+            .collect();
+        let mut i = 0;
+        while let Some(&node) = order.get(i) {
+            for next in self.neighbours(node) {
+                incoming[next as usize] -= 1;
+                if incoming[next as usize] == 0 {
+                    order.push(next);
+                }
+            }
+            i += 1;
+        }
+        (order.len() == self.node_count()).then_some(order)
+    }
+    /// Strongly connected components with Tarjan's algorithm, in reverse topological order.
+    pub fn strongly_connected_components(&self) -> Vec<Vec<u32>> {
+        const UNVISITED: usize = usize::MAX;
+        let count = self.node_count();
+        let mut index = vec![UNVISITED; count];
+        let mut low_link = vec![0; count];
+        let mut on_stack = vec![false; count];
+        let mut stack = vec![];
+        let mut components = vec![];
+        let mut next_index = 0;
+        for root in self.nodes() {
+            if index[root as usize] != UNVISITED {
+                continue;
+            }
+            // Node and how many of its edges have been followed
+            let mut call_stack = vec![(root, 0)];
+            while let Some(&mut (node, ref mut edge)) = call_stack.last_mut() {
+                let n = node as usize;
+                if *edge == 0 && index[n] == UNVISITED {
+                    index[n] = next_index;
+                    low_link[n] = next_index;
+                    next_index += 1;
+                    stack.push(node);
+                    on_stack[n] = true;
+                }
+                if let Some(&(next, _)) = self.adjacent[n].get(*edge) {
+                    *edge += 1;
+                    if index[next as usize] == UNVISITED {
+                        call_stack.push((next, 0));
+                    } else if on_stack[next as usize] {
+                        low_link[n] = low_link[n].min(index[next as usize]);
+                    }
+                    continue;
+                }
+                call_stack.pop();
+                if let Some(&(parent, _)) = call_stack.last() {
+                    low_link[parent as usize] = low_link[parent as usize].min(low_link[n]);
+                }
+                if low_link[n] == index[n] {
+                    let mut component = vec![];
+                    loop {
+                        let member = stack.pop().unwrap();
+                        on_stack[member as usize] = false;
+                        component.push(member);
+                        if member == node {
+                            break;
+                        }
+                    }
+                    components.push(component);
+                }
+            }
+        }
+        components
+    }
+    /// The graph in Graphviz's DOT format, with `labels` naming the nodes.
+    pub fn to_dot(&self, labels: &Interner) -> String {
+        let (kind, arrow) = if self.directed {
+            ("digraph", "->")
+        } else {
+            ("graph", "--")
+        };
+        let mut output = format!("{kind} {{\n");
+        for node in self.nodes() {
+            let _ = writeln!(output, "    {node} [label={:?}];", labels.label(node));
+        }
+        for (from, to, _) in self.all_edges() {
+            let _ = writeln!(output, "    {from} {arrow} {to};");
+        }
+        output.push_str("}\n");
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn directed(edges: &[(u32, u32)]) -> Graph {
+        let mut graph = Graph::new_directed();
+        for &(from, to) in edges {
+            graph.add_edge(from, to, ());
+        }
+        graph
+    }
+    fn sorted(mut components: Vec<Vec<u32>>) -> Vec<Vec<u32>> {
+        for component in &mut components {
+            component.sort_unstable();
+        }
+        components.sort_unstable();
+        components
+    }
+
+    #[test]
+    fn interner() {
+        let mut labels = Interner::new();
+        assert_eq!(labels.intern("in"), 0);
+        assert_eq!(labels.intern("out"), 1);
+        assert_eq!(labels.intern("in"), 0);
+        assert_eq!(labels.id("out"), Some(1));
+        assert_eq!(labels.id("rx"), None);
+        assert_eq!(labels.label(1), "out");
+        assert_eq!(labels.len(), 2);
+    }
+    #[test]
+    fn traversal() {
+        let graph = directed(&[(0, 1), (0, 2), (1, 3), (2, 3), (3, 4), (5, 4)]);
+        assert_eq!(graph.bfs(0), vec![0, 1, 2, 3, 4]);
+        assert_eq!(graph.dfs(0), vec![0, 1, 3, 4, 2]);
+        assert_eq!(
+            graph.distances(0),
+            vec![Some(0), Some(1), Some(1), Some(2), Some(3), None]
+        );
+        assert_eq!(graph.toposort(), Some(vec![0, 5, 1, 2, 3, 4]));
+        assert_eq!(graph.reversed().bfs(4), vec![4, 3, 5, 1, 2, 0]);
+        assert_eq!(sorted(graph.components()), vec![vec![0, 1, 2, 3, 4, 5]]);
+    }
+    #[test]
+    fn undirected() {
+        let mut graph = Graph::new_undirected();
+        graph.add_edge(0, 1, 'a');
+        graph.add_edge(2, 1, 'b');
+        graph.add_edge(3, 4, 'c');
+        graph.add_edge(4, 4, 'd');
+        assert_eq!(graph.edge_count(), 4);
+        assert_eq!(graph.all_edges().count(), graph.edge_count());
+        assert_eq!(graph.edges(1), &[(0, 'a'), (2, 'b')]);
+        assert_eq!(graph.edges(4), &[(3, 'c'), (4, 'd')]);
+        assert_eq!(sorted(graph.components()), vec![vec![0, 1, 2], vec![3, 4]]);
+        let mut labels = Interner::new();
+        for label in ["a", "b", "c", "d", "e"] {
+            labels.intern(label);
+        }
+        let dot = graph.to_dot(&labels);
+        assert!(dot.starts_with("graph {\n"));
+        assert!(dot.contains("    3 [label=\"d\"];\n"));
+        assert!(dot.contains("    1 -- 2;\n"));
+        assert_eq!(dot.matches("    4 -- 4;\n").count(), 1);
+
+        let looped = directed(&[(0, 0), (0, 1)]);
+        assert_eq!(looped.all_edges().count(), looped.edge_count());
+        assert!(looped.to_dot(&labels).contains("    0 -> 0;\n"));
+    }
+    #[test]
+    fn strongly_connected() {
+        let graph = directed(&[(0, 1), (1, 2), (2, 0), (2, 3), (3, 4), (4, 3), (5, 4)]);
+        let components = graph.strongly_connected_components();
+        assert_eq!(
+            sorted(components.clone()),
+            vec![vec![0, 1, 2], vec![3, 4], vec![5]]
+        );
+        // Components come after everything they lead to
+        let position = |node| components.iter().position(|c| c.contains(&node)).unwrap();
+        assert!(position(3) < position(0));
+        assert!(position(3) < position(5));
+        assert_eq!(graph.toposort(), None);
+    }
+}
@@ -1,5 +1,9 @@
 #![allow(dead_code)]
-use std::ops::{Add, AddAssign, Sub, SubAssign};
+use std::{
+    collections::HashMap,
+    fmt,
+    ops::{Add, AddAssign, Sub, SubAssign},
+};
 
 use ndarray::Array2;
 use num_traits::{One, WrappingAdd, WrappingSub};
@@ -233,26 +237,154 @@ impl Add<Turn> for Direction4Way {
     }
 }
 
+/// A character that doesn't stand for any cell of the grid being parsed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnknownCell(pub char);
+impl fmt::Display for UnknownCell {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Unknown grid character {:?}", self.0)
+    }
+}
+impl std::error::Error for UnknownCell {}
+
+/// A cell type that can be read from and written back to puzzle input characters.
+pub trait GridCell: Sized {
+    fn try_from_char(c: char) -> Result<Self, UnknownCell>;
+    fn to_char(&self) -> char;
+}
+impl GridCell for char {
+    fn try_from_char(c: char) -> Result<Self, UnknownCell> {
+        Ok(c)
+    }
+    fn to_char(&self) -> char {
+        *self
+    }
+}
+
+/// Positions of marker characters (like `S` or `E`) found while parsing a grid.
+#[derive(Debug, Clone, Default)]
+pub struct GridMarkers {
+    positions: HashMap<char, Vec<Position2D<usize>>>,
+}
+impl GridMarkers {
+    pub fn all(&self, marker: char) -> &[Position2D<usize>] {
+        self.positions.get(&marker).map_or(&[], Vec::as_slice)
+    }
+    pub fn first(&self, marker: char) -> Option<Position2D<usize>> {
+        self.all(marker).first().copied()
+    }
+    pub fn single(&self, marker: char) -> Result<Position2D<usize>, std::io::Error> {
+        match self.all(marker) {
+            [pos] => Ok(*pos),
+            [] => Err(std::io::Error::other(format!(
+                "Marker {marker:?} not found"
+            ))),
+            many => Err(std::io::Error::other(format!(
+                "Marker {marker:?} found {} times",
+                many.len()
+            ))),
+        }
+    }
+}
+
 pub fn parse_str_grid<T, F>(input: &str, mut mapper: F) -> Result<Array2<T>, std::io::Error>
 where
     F: FnMut(char) -> T,
 {
+    try_parse_str_grid(input, |c| Some(mapper(c)))
+}
+
+pub fn try_parse_str_grid<T, F>(input: &str, mut mapper: F) -> Result<Array2<T>, std::io::Error>
+where
+    F: FnMut(char) -> Option<T>,
+{
+    parse_str_grid_markers(input, &[], |c| mapper(c).ok_or(UnknownCell(c))).map(|(grid, _)| grid)
+}
+
+pub fn parse_grid<T: GridCell>(input: &str) -> Result<Array2<T>, std::io::Error> {
+    parse_str_grid_markers(input, &[], T::try_from_char).map(|(grid, _)| grid)
+}
+
+pub fn parse_grid_markers<T: GridCell>(
+    input: &str,
+    markers: &[char],
+) -> Result<(Array2<T>, GridMarkers), std::io::Error> {
+    parse_str_grid_markers(input, markers, T::try_from_char)
+}
+
+pub fn parse_str_grid_markers<T, F>(
+    input: &str,
+    markers: &[char],
+    mut mapper: F,
+) -> Result<(Array2<T>, GridMarkers), std::io::Error>
+where
+    F: FnMut(char) -> Result<T, UnknownCell>,
+{
+    let mut found = GridMarkers::default();
     let mut height = 0;
     let mut width = None;
     let mut grid = Vec::new();
-    for line in input.lines() {
+    for (y, line) in input.lines().enumerate() {
         height += 1;
         let mut line_width = 0;
-        for char in line.chars() {
-            grid.push(mapper(char));
+        for (x, char) in line.chars().enumerate() {
+            if markers.contains(&char) {
+                found
+                    .positions
+                    .entry(char)
+                    .or_default()
+                    .push(Position2D::new(x, y));
+            }
+            grid.push(
+                mapper(char).map_err(|e| std::io::Error::other(format!("{e} at ({x}, {y})")))?,
+            );
             line_width += 1;
         }
-        if width.is_none() {
-            width = Some(line_width);
-        } else if width.unwrap() != line_width {
-            return Err(std::io::Error::other("Input not a rectangle"));
+        match width {
+            None => width = Some(line_width),
+            Some(width) if width != line_width => {
+                return Err(std::io::Error::other("Input not a rectangle"));
+            }
+            Some(_) => {}
         }
     }
-    Array2::from_shape_vec((height, width.unwrap_or(0)), grid)
-        .map_err(|e| std::io::Error::other(format!("Array2 error: {e:?}")))
+    let grid = Array2::from_shape_vec((height, width.unwrap_or(0)), grid)
+        .map_err(|e| std::io::Error::other(format!("Array2 error: {e:?}")))?;
+    Ok((grid, found))
+}
+
+pub fn format_grid<T: GridCell>(grid: &Array2<T>) -> String {
+    let mut output = String::with_capacity(grid.len() + grid.nrows());
+    for row in grid.rows() {
+        output.extend(row.iter().map(GridCell::to_char));
+        output.push('\n');
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    const INPUT: &str = "S.#
+.#E
+..S";
+    #[test]
+    fn markers() {
+        let (grid, markers) = parse_grid_markers::<char>(INPUT, &['S', 'E']).unwrap();
+        assert_eq!(grid.shape(), [3, 3]);
+        assert_eq!(
+            markers.all('S'),
+            [Position2D::new(0, 0), Position2D::new(2, 2)]
+        );
+        assert_eq!(markers.single('E').unwrap(), Position2D::new(2, 1));
+        assert!(markers.single('S').is_err());
+        assert!(markers.single('#').is_err());
+        assert_eq!(format_grid(&grid), format!("{INPUT}\n"));
+    }
+    #[test]
+    fn unknown_char() {
+        let err = try_parse_str_grid(INPUT, |c| (c != 'E').then_some(c)).unwrap_err();
+        assert_eq!(err.to_string(), "Unknown grid character 'E' at (2, 1)");
+        assert_eq!(UnknownCell('E').to_string(), "Unknown grid character 'E'");
+    }
 }
@@ -0,0 +1,234 @@
+#![allow(dead_code)]
+use std::ops::{Add, AddAssign, Mul, Sub, SubAssign};
+
+use num_traits::One;
+
+use super::grid2d::Position2D;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Position3D<T> {
+    pub x: T,
+    pub y: T,
+    pub z: T,
+}
+impl<T: Copy> Position3D<T> {
+    pub const fn new(x: T, y: T, z: T) -> Self {
+        Self { x, y, z }
+    }
+    pub const fn new_xyz(xyz: (T, T, T)) -> Self {
+        Self {
+            x: xyz.0,
+            y: xyz.1,
+            z: xyz.2,
+        }
+    }
+    pub const fn xyz(&self) -> (T, T, T) {
+        (self.x, self.y, self.z)
+    }
+    pub const fn project_xy(&self) -> Position2D<T> {
+        Position2D::new(self.x, self.y)
+    }
+    pub const fn project_xz(&self) -> Position2D<T> {
+        Position2D::new(self.x, self.z)
+    }
+    pub const fn project_yz(&self) -> Position2D<T> {
+        Position2D::new(self.y, self.z)
+    }
+    pub fn map<U>(&self, mut f: impl FnMut(T) -> U) -> Position3D<U> {
+        Position3D {
+            x: f(self.x),
+            y: f(self.y),
+            z: f(self.z),
+        }
+    }
+}
+impl<T: Copy + Add<Output = T>> Position3D<T> {
+    pub fn add_x(&self, x: T) -> Self {
+        Self {
+            x: self.x + x,
+            ..*self
+        }
+    }
+    pub fn add_y(&self, y: T) -> Self {
+        Self {
+            y: self.y + y,
+            ..*self
+        }
+    }
+    pub fn add_z(&self, z: T) -> Self {
+        Self {
+            z: self.z + z,
+            ..*self
+        }
+    }
+}
+impl<T: Copy + Sub<Output = T>> Position3D<T> {
+    pub fn sub_x(&self, x: T) -> Self {
+        Self {
+            x: self.x - x,
+            ..*self
+        }
+    }
+    pub fn sub_y(&self, y: T) -> Self {
+        Self {
+            y: self.y - y,
+            ..*self
+        }
+    }
+    pub fn sub_z(&self, z: T) -> Self {
+        Self {
+            z: self.z - z,
+            ..*self
+        }
+    }
+}
+
+impl<T: Copy + PartialOrd + Sub<Output = T> + Add<Output = T>> Position3D<T> {
+    pub fn manhattan(&self, other: &Self) -> T {
+        let diff = |a: T, b: T| if a > b { a - b } else { b - a };
+        diff(self.x, other.x) + diff(self.y, other.y) + diff(self.z, other.z)
+    }
+}
+impl<T: Copy + Add<Output = T> + Sub<Output = T> + Mul<Output = T>> Position3D<T> {
+    pub fn dot(&self, other: &Self) -> T {
+        self.x * other.x + self.y * other.y + self.z * other.z
+    }
+    pub fn cross(&self, other: &Self) -> Self {
+        Self {
+            x: self.y * other.z - self.z * other.y,
+            y: self.z * other.x - self.x * other.z,
+            z: self.x * other.y - self.y * other.x,
+        }
+    }
+}
+
+impl<T: Add<Output = T>> Add<Self> for Position3D<T> {
+    type Output = Self;
+    fn add(self, other: Self) -> Self {
+        Self {
+            x: self.x + other.x,
+            y: self.y + other.y,
+            z: self.z + other.z,
+        }
+    }
+}
+impl<T: AddAssign> AddAssign for Position3D<T> {
+    fn add_assign(&mut self, other: Self) {
+        self.x += other.x;
+        self.y += other.y;
+        self.z += other.z;
+    }
+}
+
+impl<T: Sub<Output = T>> Sub<Self> for Position3D<T> {
+    type Output = Self;
+    fn sub(self, other: Self) -> Self {
+        Self {
+            x: self.x - other.x,
+            y: self.y - other.y,
+            z: self.z - other.z,
+        }
+    }
+}
+impl<T: SubAssign> SubAssign for Position3D<T> {
+    fn sub_assign(&mut self, other: Self) {
+        self.x -= other.x;
+        self.y -= other.y;
+        self.z -= other.z;
+    }
+}
+
+impl<T: Copy + Mul<Output = T>> Mul<T> for Position3D<T> {
+    type Output = Self;
+    fn mul(self, scalar: T) -> Self {
+        Self {
+            x: self.x * scalar,
+            y: self.y * scalar,
+            z: self.z * scalar,
+        }
+    }
+}
+
+/// Axis-aligned box where both corners are inclusive.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Box3D<T> {
+    pub min: Position3D<T>,
+    pub max: Position3D<T>,
+}
+impl<T: Copy + Ord> Box3D<T> {
+    pub fn new(a: Position3D<T>, b: Position3D<T>) -> Self {
+        Self {
+            min: Position3D::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z)),
+            max: Position3D::new(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z)),
+        }
+    }
+    pub fn contains(&self, pos: &Position3D<T>) -> bool {
+        self.min.x <= pos.x
+            && pos.x <= self.max.x
+            && self.min.y <= pos.y
+            && pos.y <= self.max.y
+            && self.min.z <= pos.z
+            && pos.z <= self.max.z
+    }
+    pub fn intersection(&self, other: &Self) -> Option<Self> {
+        let min = Position3D::new(
+            self.min.x.max(other.min.x),
+            self.min.y.max(other.min.y),
+            self.min.z.max(other.min.z),
+        );
+        let max = Position3D::new(
+            self.max.x.min(other.max.x),
+            self.max.y.min(other.max.y),
+            self.max.z.min(other.max.z),
+        );
+        (min.x <= max.x && min.y <= max.y && min.z <= max.z).then_some(Self { min, max })
+    }
+    pub fn intersects(&self, other: &Self) -> bool {
+        self.intersection(other).is_some()
+    }
+}
+impl<T: Copy + Ord + One + Add<Output = T> + Sub<Output = T> + Mul<Output = T>> Box3D<T> {
+    pub fn size(&self) -> Position3D<T> {
+        self.max - self.min + Position3D::new(T::one(), T::one(), T::one())
+    }
+    pub fn volume(&self) -> T {
+        let size = self.size();
+        size.x * size.y * size.z
+    }
+}
+impl<T: Copy + Add<Output = T>> Box3D<T> {
+    pub fn translate(&self, by: Position3D<T>) -> Self {
+        Self {
+            min: self.min + by,
+            max: self.max + by,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    type Pos = Position3D<i64>;
+    #[test]
+    fn products() {
+        let a = Pos::new(1, 2, 3);
+        let b = Pos::new(4, 5, 6);
+        assert_eq!(a.dot(&b), 32);
+        assert_eq!(a.cross(&b), Pos::new(-3, 6, -3));
+        assert_eq!(a.cross(&b).dot(&a), 0);
+        assert_eq!(a.manhattan(&b), 9);
+        assert_eq!(b.project_xz(), Position2D::new(4, 6));
+    }
+    #[test]
+    fn boxes() {
+        let a = Box3D::new(Pos::new(2, 0, 0), Pos::new(0, 2, 2));
+        let b = Box3D::new(Pos::new(1, 1, 1), Pos::new(3, 3, 3));
+        assert_eq!(a.volume(), 27);
+        assert_eq!(
+            a.intersection(&b),
+            Some(Box3D::new(Pos::new(1, 1, 1), Pos::new(2, 2, 2)))
+        );
+        assert!(!a.intersects(&b.translate(Pos::new(0, 0, 2))));
+        assert!(a.contains(&Pos::new(0, 2, 1)));
+    }
+}
@@ -0,0 +1,198 @@
+#![allow(dead_code)]
+use std::collections::HashMap;
+
+use ndarray::Array2;
+
+use super::{
+    graph::Graph,
+    grid2d::{Direction4Way, Position2D},
+};
+
+type Pos = Position2D<usize>;
+type Dir = Direction4Way;
+
+/// How a grid cell can be walked through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CellWalk {
+    Open,
+    Blocked,
+    /// Can only be entered and left going this way
+    OneWay(Dir),
+}
+
+/// Cells between two junctions, not including either of them.
+#[derive(Debug, Clone)]
+pub struct Corridor {
+    pub cells: Vec<Pos>,
+}
+impl Corridor {
+    /// Steps from one junction to the other.
+    pub const fn length(&self) -> usize {
+        self.cells.len() + 1
+    }
+}
+
+/// A grid squashed into the cells where paths split, and the corridors joining them.
+#[derive(Debug, Clone)]
+pub struct Junctions {
+    positions: Vec<Pos>,
+    ids: HashMap<Pos, u32>,
+    graph: Graph<Corridor>,
+}
+impl Junctions {
+    /// Finds every open cell with more than two open neighbours, plus the ones in `keep`, and
+    /// follows the corridors leaving them. One-way cells make the corridor one-way.
+    pub fn compress<T>(grid: &Array2<T>, walk: impl Fn(&T) -> CellWalk, keep: &[Pos]) -> Self {
+        let walk_at = |pos: Pos| grid.get(pos.yx()).map_or(CellWalk::Blocked, &walk);
+        let can_step = |from: Pos, dir: Dir| {
+            let to = from + dir;
+            let allowed = |cell| match cell {
+                CellWalk::Open => true,
+                CellWalk::Blocked => false,
+                CellWalk::OneWay(way) => way == dir,
+            };
+            allowed(walk_at(from)) && allowed(walk_at(to))
+        };
+        let open_neighbours = |pos: Pos| {
+            Dir::EVERY
+                .into_iter()
+                .filter(|&dir| walk_at(pos + dir) != CellWalk::Blocked)
+                .count()
+        };
+
+        let mut positions: Vec<Pos> = keep.to_vec();
+        for ((y, x), cell) in grid.indexed_iter() {
+            let pos = Pos::new(x, y);
+            if walk(cell) != CellWalk::Blocked && open_neighbours(pos) > 2 && !keep.contains(&pos) {
+                positions.push(pos);
+            }
+        }
+        let ids: HashMap<Pos, u32> = positions
+            .iter()
+            .enumerate()
+            .map(|(id, &pos)| (pos, u32::try_from(id).unwrap()))
+            .collect();
+
+        let mut graph = Graph::new_directed();
+        graph.ensure_node(u32::try_from(positions.len()).unwrap().saturating_sub(1));
+        for (&junction, id) in positions.iter().zip(0..) {
+            'corridor: for first in Dir::EVERY {
+                let mut dir = first;
+                let mut pos = junction;
+                let mut cells = vec![];
+                loop {
+                    if !can_step(pos, dir) {
+                        continue 'corridor;
+                    }
+                    pos = pos + dir;
+                    if let Some(&to) = ids.get(&pos) {
+                        graph.add_edge(id, to, Corridor { cells });
+                        continue 'corridor;
+                    }
+                    cells.push(pos);
+                    let back = dir.turn_right(2);
+                    let Some(next) = Dir::EVERY
+                        .into_iter()
+                        .find(|&next| next != back && walk_at(pos + next) != CellWalk::Blocked)
+                    else {
+                        // Dead end
+                        continue 'corridor;
+                    };
+                    dir = next;
+                }
+            }
+        }
+        Self {
+            positions,
+            ids,
+            graph,
+        }
+    }
+    pub const fn graph(&self) -> &Graph<Corridor> {
+        &self.graph
+    }
+    pub fn id(&self, pos: Pos) -> Option<u32> {
+        self.ids.get(&pos).copied()
+    }
+    pub fn position(&self, id: u32) -> Pos {
+        self.positions[id as usize]
+    }
+    /// Every cell along a path going through the junctions `nodes`, taking the longest
+    /// corridor where there are several between two junctions.
+    pub fn expand(&self, nodes: &[u32]) -> Vec<Pos> {
+        let mut cells = nodes
+            .first()
+            .map(|&id| self.position(id))
+            .into_iter()
+            .collect::<Vec<_>>();
+        for pair in nodes.windows(2) {
+            let corridor = self
+                .graph
+                .edges(pair[0])
+                .iter()
+                .filter(|&&(to, _)| to == pair[1])
+                .map(|(_, corridor)| corridor)
+                .max_by_key(|corridor| corridor.length())
+                .expect("Junctions aren't connected");
+            cells.extend(&corridor.cells);
+            cells.push(self.position(pair[1]));
+        }
+        cells
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::grid2d::parse_grid;
+
+    #[allow(clippy::trivially_copy_pass_by_ref)]
+    fn cell_walk(c: &char) -> CellWalk {
+        match c {
+            '#' => CellWalk::Blocked,
+            '>' => CellWalk::OneWay(Dir::East),
+            _ => CellWalk::Open,
+        }
+    }
+
+    #[test]
+    fn compress() {
+        let grid: Array2<char> = parse_grid(
+            "#.###
+#.>.#
+#.#.#
+#...#
+###.#",
+        )
+        .unwrap();
+        let (start, end) = (Pos::new(1, 0), Pos::new(3, 4));
+        let junctions = Junctions::compress(&grid, cell_walk, &[start, end]);
+        let split = junctions.id(Pos::new(1, 1)).unwrap();
+        let join = junctions.id(Pos::new(3, 3)).unwrap();
+        let graph = junctions.graph();
+        assert_eq!(graph.node_count(), 4);
+
+        let lengths = |from, to| {
+            let mut lengths: Vec<usize> = graph
+                .edges(from)
+                .iter()
+                .filter(|&&(next, _)| next == to)
+                .map(|(_, corridor)| corridor.length())
+                .collect();
+            lengths.sort_unstable();
+            lengths
+        };
+        assert_eq!(lengths(0, split), vec![1]);
+        assert_eq!(lengths(split, join), vec![4, 4]);
+        // The slope only lets one of the corridors go back
+        assert_eq!(lengths(join, split), vec![4]);
+        assert_eq!(lengths(join, 1), vec![1]);
+
+        let cells = junctions.expand(&[0, split, join, 1]);
+        assert_eq!(cells.len(), 7);
+        assert_eq!((cells[0], cells[6]), (start, end));
+        assert!(cells
+            .windows(2)
+            .all(|pair| pair[0].manhattan(&pair[1]) == 1));
+    }
+}
@@ -0,0 +1,231 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+use super::graph::Graph;
+
+/// Partial paths are expanded breadth-first until there are this many to share between
+/// threads.
+const FAN_OUT: usize = 64;
+
+/// A path found so far, with the nodes it went through.
+#[derive(Debug, Clone)]
+struct Walk {
+    node: u32,
+    visited: u128,
+    length: usize,
+    /// Most the rest of the path could add, from the heaviest edge into each unvisited node
+    bound: usize,
+    path: Vec<u32>,
+}
+
+struct Search<'a, E, W> {
+    graph: &'a Graph<E>,
+    weight: W,
+    end: u32,
+    max_incoming: Vec<usize>,
+    /// Node every path to the end has to come from, if there's only one
+    before_end: Option<u32>,
+    best: AtomicUsize,
+}
+impl<E, W: Fn(&E) -> usize> Search<'_, E, W> {
+    fn next_steps<'b>(&'b self, walk: &'b Walk) -> impl Iterator<Item = Walk> + 'b {
+        self.graph
+            .edges(walk.node)
+            .iter()
+            .filter(move |&&(next, _)| {
+                walk.visited & (1 << next) == 0
+                    && (self.before_end != Some(walk.node) || next == self.end)
+            })
+            .map(move |(next, edge)| {
+                let mut path = walk.path.clone();
+                path.push(*next);
+                Walk {
+                    node: *next,
+                    visited: walk.visited | (1 << next),
+                    length: walk.length + (self.weight)(edge),
+                    bound: walk.bound - self.max_incoming[*next as usize],
+                    path,
+                }
+            })
+    }
+    fn record(&self, walk: &Walk, best: &mut Option<(usize, Vec<u32>)>) {
+        if best
+            .as_ref()
+            .is_none_or(|(length, _)| walk.length > *length)
+        {
+            *best = Some((walk.length, walk.path.clone()));
+            self.best.fetch_max(walk.length, Ordering::Relaxed);
+        }
+    }
+    fn dfs(&self, walk: &mut Walk, best: &mut Option<(usize, Vec<u32>)>) {
+        if walk.node == self.end {
+            self.record(walk, best);
+            return;
+        }
+        if walk.length + walk.bound < self.best.load(Ordering::Relaxed) {
+            return;
+        }
+        let node = walk.node;
+        for (next, edge) in self.graph.edges(node) {
+            if walk.visited & (1 << next) != 0
+                || (self.before_end == Some(node) && *next != self.end)
+            {
+                continue;
+            }
+            let step = (self.weight)(edge);
+            let incoming = self.max_incoming[*next as usize];
+            walk.visited |= 1 << next;
+            walk.length += step;
+            walk.bound -= incoming;
+            walk.path.push(*next);
+            walk.node = *next;
+            self.dfs(walk, best);
+            walk.node = node;
+            walk.path.pop();
+            walk.bound += incoming;
+            walk.length -= step;
+            walk.visited &= !(1 << next);
+        }
+    }
+}
+
+/// Longest path from `start` to `end` that visits every node at most once, with its length
+/// and nodes.
+///
+/// Searches depth first with the visited nodes as a bitmask, so the graph can have at most
+/// 128 nodes. Partial paths that can't beat the best one found so far are pruned.
+pub fn longest_path<E, W>(
+    graph: &Graph<E>,
+    start: u32,
+    end: u32,
+    weight: W,
+) -> Option<(usize, Vec<u32>)>
+where
+    E: Sync,
+    W: Fn(&E) -> usize + Sync,
+{
+    assert!(
+        graph.node_count() <= 128,
+        "Too many nodes for a u128 bitmask"
+    );
+    let mut max_incoming = vec![0; graph.node_count()];
+    let mut predecessors = vec![vec![]; graph.node_count()];
+    for (from, to, edge) in graph.all_edges() {
+        let targets: &[(u32, u32)] = if graph.is_directed() {
+            &[(from, to)]
+        } else {
+            &[(from, to), (to, from)]
+        };
+        for &(from, to) in targets {
+            max_incoming[to as usize] = max_incoming[to as usize].max(weight(edge));
+            if !predecessors[to as usize].contains(&from) {
+                predecessors[to as usize].push(from);
+            }
+        }
+    }
+    let before_end = match predecessors[end as usize][..] {
+        [only] if only != start => Some(only),
+        _ => None,
+    };
+    let bound = max_incoming.iter().sum::<usize>() - max_incoming[start as usize];
+    let search = Search {
+        graph,
+        weight,
+        end,
+        max_incoming,
+        before_end,
+        best: AtomicUsize::new(0),
+    };
+
+    let mut best = None;
+    let mut frontier = vec![Walk {
+        node: start,
+        visited: 1 << start,
+        length: 0,
+        bound,
+        path: vec![start],
+    }];
+    while !frontier.is_empty() && frontier.len() < FAN_OUT {
+        let mut next_frontier = vec![];
+        for walk in &frontier {
+            if walk.node == end {
+                search.record(walk, &mut best);
+            } else {
+                next_frontier.extend(search.next_steps(walk));
+            }
+        }
+        frontier = next_frontier;
+    }
+    frontier
+        .into_par_iter()
+        .filter_map(|mut walk| {
+            let mut best = None;
+            search.dfs(&mut walk, &mut best);
+            best
+        })
+        .chain(best)
+        .max_by_key(|(length, _)| *length)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn weighted() {
+        // 0 -> 3 directly is heaviest, but going round through 1 and 2 is longer
+        let mut graph = Graph::new_undirected();
+        for (from, to, weight) in [(0, 1, 2), (1, 2, 2), (2, 3, 2), (0, 3, 5), (1, 3, 1)] {
+            graph.add_edge(from, to, weight);
+        }
+        assert_eq!(
+            longest_path(&graph, 0, 3, |&w| w),
+            Some((6, vec![0, 1, 2, 3]))
+        );
+        assert_eq!(longest_path(&graph, 0, 0, |&w| w), Some((0, vec![0])));
+
+        let mut directed = Graph::new_directed();
+        directed.add_edge(0, 1, 1);
+        directed.add_edge(2, 1, 1);
+        assert_eq!(longest_path(&directed, 0, 2, |&w| w), None);
+    }
+    fn brute(graph: &Graph<usize>, node: u32, end: u32, visited: u32) -> Option<usize> {
+        if node == end {
+            return Some(0);
+        }
+        graph
+            .edges(node)
+            .iter()
+            .filter(|(next, _)| visited & (1 << next) == 0)
+            .filter_map(|&(next, w)| brute(graph, next, end, visited | (1 << next)).map(|l| l + w))
+            .max()
+    }
+    #[test]
+    fn against_brute_force() {
+        // 3x3 grid with a different weight on every edge
+        let edges = [
+            (0, 1),
+            (1, 2),
+            (0, 3),
+            (1, 4),
+            (2, 5),
+            (3, 4),
+            (4, 5),
+            (3, 6),
+            (4, 7),
+            (5, 8),
+            (6, 7),
+            (7, 8),
+        ];
+        let mut graph = Graph::new_undirected();
+        for (i, &(from, to)) in edges.iter().enumerate() {
+            graph.add_edge(from, to, i + 1);
+        }
+        for end in 1..9 {
+            let (length, path) = longest_path(&graph, 0, end, |&w| w).unwrap();
+            assert_eq!(Some(length), brute(&graph, 0, end, 1));
+            assert_eq!((path[0], *path.last().unwrap()), (0, end));
+        }
+    }
+}
@@ -1,7 +1,27 @@
 #![allow(dead_code)]
+pub mod bitgrid;
+pub mod cycle;
+pub mod graph;
 pub mod grid2d;
+pub mod grid3d;
+pub mod junctions;
+pub mod longest_path;
+pub mod nonogram;
+pub mod number_theory;
+pub mod piecewise;
+pub mod polygon;
+pub mod polynomial;
+pub mod ranges;
+#[cfg(test)]
+pub mod test_rng;
+pub mod tiled_grid;
+pub mod token_scanner;
 
-use std::ops::{Range, RangeBounds};
+pub use ranges::RangeIntersect;
+
+use std::ops::{
+    BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not, Shl, Shr,
+};
 pub trait BorrowTwo<T> {
     fn borrow_two(&mut self, a: usize, b: usize) -> (&mut T, &mut T);
 }
@@ -21,17 +41,32 @@ impl<T> BorrowTwo<T> for [T] {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct LongBitArr<const SIZE: usize> {
     data: [u64; SIZE],
 }
 impl<const SIZE: usize> LongBitArr<SIZE> {
     const _SIZE_CHECK: () = assert!(!(SIZE == 0), "Size can't be 0");
+    pub const BITS: usize = SIZE * 64;
     #[allow(clippy::let_unit_value)]
     pub const fn new() -> Self {
         let () = Self::_SIZE_CHECK;
         Self { data: [0; SIZE] }
     }
+    /// Array with the bits `0..len` set.
+    pub fn with_first(len: usize) -> Self {
+        assert!(len <= Self::BITS);
+        let mut new = Self::new();
+        for (i, word) in new.data.iter_mut().enumerate() {
+            let bits = len.saturating_sub(i * 64).min(64);
+            *word = if bits == 64 {
+                u64::MAX
+            } else {
+                (1 << bits) - 1
+            };
+        }
+        new
+    }
     pub fn get(&self, index: usize) -> bool {
         assert!(index < SIZE * 64);
         unsafe { (self.data.get_unchecked(index / 64) >> (index % 64)) & 1 != 0 }
@@ -48,9 +83,171 @@ impl<const SIZE: usize> LongBitArr<SIZE> {
             *self.data.get_unchecked_mut(index / 64) &= !(1 << (index % 64));
         }
     }
+    /// Sets the bit, returning `true` if it was not set before. Mirrors `HashSet::insert`.
+    pub fn insert(&mut self, index: usize) -> bool {
+        let new = !self.get(index);
+        self.set(index);
+        new
+    }
     pub fn count_ones(&self) -> usize {
         self.data.iter().map(|d| d.count_ones() as usize).sum()
     }
+    pub fn is_empty(&self) -> bool {
+        self.data.iter().all(|&d| d == 0)
+    }
+    pub fn first_set(&self) -> Option<usize> {
+        self.data
+            .iter()
+            .enumerate()
+            .find(|(_, &d)| d != 0)
+            .map(|(i, d)| i * 64 + d.trailing_zeros() as usize)
+    }
+    pub fn last_set(&self) -> Option<usize> {
+        self.data
+            .iter()
+            .enumerate()
+            .rev()
+            .find(|(_, &d)| d != 0)
+            .map(|(i, d)| i * 64 + 63 - d.leading_zeros() as usize)
+    }
+    pub fn union(&self, other: &Self) -> Self {
+        *self | *other
+    }
+    pub fn intersection(&self, other: &Self) -> Self {
+        *self & *other
+    }
+    pub fn difference(&self, other: &Self) -> Self {
+        *self & !*other
+    }
+    pub fn symmetric_difference(&self, other: &Self) -> Self {
+        *self ^ *other
+    }
+    pub const fn iter(&self) -> LongBitIterator<SIZE> {
+        LongBitIterator {
+            data: self.data,
+            word: 0,
+        }
+    }
+}
+impl<const SIZE: usize> Default for LongBitArr<SIZE> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl<const SIZE: usize> BitArray for LongBitArr<SIZE> {
+    type Index = usize;
+    fn get(&self, index: usize) -> bool {
+        Self::get(self, index)
+    }
+    fn set(&mut self, index: usize) {
+        Self::set(self, index);
+    }
+    fn clear(&mut self, index: usize) {
+        Self::clear(self, index);
+    }
+}
+macro_rules! impl_LongBitArr_op {
+    ($($op:ident, $op_fn:ident, $assign:ident, $assign_fn:ident);+) => {
+        $(impl<const SIZE: usize> $op for LongBitArr<SIZE> {
+            type Output = Self;
+            fn $op_fn(mut self, other: Self) -> Self {
+                self.$assign_fn(other);
+                self
+            }
+        }
+        impl<const SIZE: usize> $assign for LongBitArr<SIZE> {
+            fn $assign_fn(&mut self, other: Self) {
+                for (a, b) in self.data.iter_mut().zip(other.data) {
+                    a.$assign_fn(b);
+                }
+            }
+        })*
+    }
+}
+impl_LongBitArr_op!(
+    BitAnd, bitand, BitAndAssign, bitand_assign;
+    BitOr, bitor, BitOrAssign, bitor_assign;
+    BitXor, bitxor, BitXorAssign, bitxor_assign
+);
+impl<const SIZE: usize> Not for LongBitArr<SIZE> {
+    type Output = Self;
+    fn not(mut self) -> Self {
+        for d in &mut self.data {
+            *d = !*d;
+        }
+        self
+    }
+}
+/// Moves every bit to a higher index, dropping bits shifted past the end.
+impl<const SIZE: usize> Shl<usize> for LongBitArr<SIZE> {
+    type Output = Self;
+    fn shl(self, by: usize) -> Self {
+        let (words, bits) = (by / 64, by % 64);
+        let mut new = Self::new();
+        for i in words..SIZE {
+            let from = i - words;
+            new.data[i] = self.data[from] << bits;
+            if bits > 0 && from > 0 {
+                new.data[i] |= self.data[from - 1] >> (64 - bits);
+            }
+        }
+        new
+    }
+}
+/// Moves every bit to a lower index, dropping bits shifted below zero.
+impl<const SIZE: usize> Shr<usize> for LongBitArr<SIZE> {
+    type Output = Self;
+    fn shr(self, by: usize) -> Self {
+        let (words, bits) = (by / 64, by % 64);
+        let mut new = Self::new();
+        for i in 0..SIZE.saturating_sub(words) {
+            let from = i + words;
+            new.data[i] = self.data[from] >> bits;
+            if bits > 0 && from + 1 < SIZE {
+                new.data[i] |= self.data[from + 1] << (64 - bits);
+            }
+        }
+        new
+    }
+}
+impl<const SIZE: usize> IntoIterator for &LongBitArr<SIZE> {
+    type Item = usize;
+    type IntoIter = LongBitIterator<SIZE>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+impl<const SIZE: usize> FromIterator<usize> for LongBitArr<SIZE> {
+    fn from_iter<Q>(iter: Q) -> Self
+    where
+        Q: IntoIterator<Item = usize>,
+    {
+        let mut new = Self::new();
+        for index in iter {
+            new.set(index);
+        }
+        new
+    }
+}
+
+pub struct LongBitIterator<const SIZE: usize> {
+    data: [u64; SIZE],
+    word: usize,
+}
+impl<const SIZE: usize> Iterator for LongBitIterator<SIZE> {
+    type Item = usize;
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.word < SIZE {
+            let d = &mut self.data[self.word];
+            if *d != 0 {
+                let next = d.trailing_zeros();
+                *d ^= 1 << next;
+                return Some(self.word * 64 + next as usize);
+            }
+            self.word += 1;
+        }
+        None
+    }
 }
 
 pub struct BitIterator<T>(T);
@@ -64,6 +261,7 @@ macro_rules! impl_BitIterator {
                 } else {
                     let next = self.0.trailing_zeros();
                     self.0 ^= 1 << next;
+                    #[allow(clippy::cast_possible_truncation)]
                     Some(next as $t)
                 }
             }
@@ -89,13 +287,15 @@ macro_rules! impl_IntoBitIterator {
 impl_IntoBitIterator!(usize, u8, u16, u32, u64, u128);
 
 pub trait BitArray {
-    fn get(&self, index: Self) -> bool;
-    fn set(&mut self, index: Self);
-    fn clear(&mut self, index: Self);
+    type Index;
+    fn get(&self, index: Self::Index) -> bool;
+    fn set(&mut self, index: Self::Index);
+    fn clear(&mut self, index: Self::Index);
 }
 macro_rules! impl_BitArray {
     ($($t:ty),+) => {
         $(impl BitArray for $t {
+            type Index = Self;
             fn get(&self, index: Self) -> bool {
                 (self >> index) & 1 != 0
             }
@@ -110,19 +310,6 @@ macro_rules! impl_BitArray {
 }
 impl_BitArray!(usize, u8, u16, u32, u64, u128);
 
-pub trait RangeIntersect<T: Ord, U: RangeBounds<T>>: RangeBounds<T> {
-    fn intersect(&self, other: &U) -> Option<U>;
-}
-impl<T: Ord + Copy> RangeIntersect<T, Self> for Range<T> {
-    fn intersect(&self, other: &Self) -> Option<Self> {
-        if self.end < other.start || other.end < self.start {
-            None
-        } else {
-            Some(self.start.max(other.start)..self.end.min(other.end))
-        }
-    }
-}
-
 #[derive(Debug, Clone)]
 pub struct FirstAndLast<T> {
     first: Option<T>,
@@ -149,47 +336,26 @@ impl<T: Clone> FirstAndLast<T> {
     }
 }
 
-#[derive(Debug, Clone)]
-pub struct MergedRange<T> {
-    ranges: Vec<Range<T>>,
-}
-impl<T: Ord + Copy> MergedRange<T> {
-    pub const fn new() -> Self {
-        Self { ranges: vec![] }
-    }
-    pub fn add(&mut self, other: Range<T>) {
-        self.ranges.push(other);
-        if self.ranges.len() > 1 {
-            self.simplify();
-        }
-    }
-    fn simplify(&mut self) {
-        self.ranges.sort_unstable_by(|a, b| a.start.cmp(&b.start));
-        'outer: loop {
-            for index in 0..self.ranges.len() - 1 {
-                let (a, b) = self.ranges.borrow_two(index, index + 1);
-                if a.end >= b.start && a.start <= b.end {
-                    *a = a.start.min(b.start)..a.end.max(b.end);
-                    self.ranges.remove(index + 1);
-                    continue 'outer;
-                }
-            }
-            break;
-        }
-    }
-    pub fn ranges(&self) -> Vec<Range<T>> {
-        self.ranges.clone()
-    }
-}
-impl<T: Ord + Copy> FromIterator<Range<T>> for MergedRange<T> {
-    fn from_iter<Q>(iter: Q) -> Self
-    where
-        Q: IntoIterator<Item = Range<T>>,
-    {
-        let mut new = Self::new();
-        for range in iter {
-            new.add(range);
-        }
-        new
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn long_bit_arr() {
+        let a: LongBitArr<2> = [1, 5, 63, 64, 100].into_iter().collect();
+        let b: LongBitArr<2> = [5, 64, 127].into_iter().collect();
+        assert_eq!((a | b).iter().collect::<Vec<_>>(), [1, 5, 63, 64, 100, 127]);
+        assert_eq!(a.intersection(&b).iter().collect::<Vec<_>>(), [5, 64]);
+        assert_eq!(a.difference(&b).iter().collect::<Vec<_>>(), [1, 63, 100]);
+        assert_eq!(a.symmetric_difference(&b).count_ones(), 4);
+        assert_eq!((a << 1).iter().collect::<Vec<_>>(), [2, 6, 64, 65, 101]);
+        assert_eq!((a >> 2).iter().collect::<Vec<_>>(), [3, 61, 62, 98]);
+        assert_eq!((b << 70).iter().collect::<Vec<_>>(), [75]);
+        assert_eq!((a >> 64).iter().collect::<Vec<_>>(), [0, 36]);
+        assert_eq!((a.first_set(), a.last_set()), (Some(1), Some(100)));
+        assert_eq!(LongBitArr::<2>::new().first_set(), None);
+        assert_eq!(LongBitArr::<2>::with_first(70).count_ones(), 70);
+        let mut visited = LongBitArr::<2>::new();
+        assert!(visited.insert(3));
+        assert!(!visited.insert(3));
     }
 }
@@ -0,0 +1,416 @@
+// The helpers module allows dead code, but only tests use the puzzle solver so far
+#![warn(dead_code)]
+use std::{collections::VecDeque, fmt};
+
+use ndarray::{Array2, ArrayViewMut1, Axis};
+
+use super::grid2d::{GridCell, UnknownCell};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Cell {
+    Empty,
+    Filled,
+    Unknown,
+}
+impl GridCell for Cell {
+    fn try_from_char(c: char) -> Result<Self, UnknownCell> {
+        Ok(match c {
+            '.' => Self::Empty,
+            '#' => Self::Filled,
+            '?' => Self::Unknown,
+            _ => return Err(UnknownCell(c)),
+        })
+    }
+    fn to_char(&self) -> char {
+        match self {
+            Self::Empty => '.',
+            Self::Filled => '#',
+            Self::Unknown => '?',
+        }
+    }
+}
+
+/// A clue asking for a run of no cells, which can't be told apart from no run at all.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EmptyRun;
+impl fmt::Display for EmptyRun {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Runs can't be empty")
+    }
+}
+impl std::error::Error for EmptyRun {}
+
+/// Ways of placing the runs of a line, counted from every point where a run could start.
+///
+/// State `(i, j)` is being at cell `i` with the first `j` runs placed, with cell `i - 1`
+/// empty (or `i` being the start of the line). From there either cell `i` is empty, or run
+/// `j` starts at it and the cell after it is empty.
+struct LineDp<'a> {
+    cells: &'a [Cell],
+    runs: &'a [usize],
+    /// Empty cells before each position, to check whether a run fits in constant time
+    empty_before: Vec<usize>,
+}
+impl<'a> LineDp<'a> {
+    fn new(cells: &'a [Cell], runs: &'a [usize]) -> Result<Self, EmptyRun> {
+        if runs.contains(&0) {
+            return Err(EmptyRun);
+        }
+        let empty_before = std::iter::once(0)
+            .chain(cells.iter().scan(0, |count, &cell| {
+                *count += usize::from(cell == Cell::Empty);
+                Some(*count)
+            }))
+            .collect();
+        Ok(Self {
+            cells,
+            runs,
+            empty_before,
+        })
+    }
+    /// State reached by starting run `j` at cell `i`, if it fits there.
+    fn place(&self, i: usize, j: usize) -> Option<usize> {
+        let end = i + *self.runs.get(j)?;
+        if end > self.cells.len()
+            || self.empty_before[end] != self.empty_before[i]
+            || self.cells.get(end) == Some(&Cell::Filled)
+        {
+            return None;
+        }
+        Some((end + 1).min(self.cells.len()))
+    }
+    fn can_skip(&self, i: usize) -> bool {
+        self.cells[i] != Cell::Filled
+    }
+    /// `ways[i][j]` is the number of ways to finish the line from state `(i, j)`, saturating
+    /// instead of overflowing.
+    fn ways_to_end(&self) -> Vec<Vec<usize>> {
+        let (n, k) = (self.cells.len(), self.runs.len());
+        let mut ways = vec![vec![0usize; k + 1]; n + 1];
+        ways[n][k] = 1;
+        for i in (0..n).rev() {
+            for j in 0..=k {
+                let mut total = 0usize;
+                if self.can_skip(i) {
+                    total = total.saturating_add(ways[i + 1][j]);
+                }
+                if let Some(next) = self.place(i, j) {
+                    total = total.saturating_add(ways[next][j + 1]);
+                }
+                ways[i][j] = total;
+            }
+        }
+        ways
+    }
+    /// Whether each state can be reached from the start of the line.
+    #[allow(dead_code)]
+    fn reachable(&self) -> Vec<Vec<bool>> {
+        let (n, k) = (self.cells.len(), self.runs.len());
+        let mut reached = vec![vec![false; k + 1]; n + 1];
+        reached[0][0] = true;
+        for i in 0..n {
+            for j in 0..=k {
+                if !reached[i][j] {
+                    continue;
+                }
+                if self.can_skip(i) {
+                    reached[i + 1][j] = true;
+                }
+                if let Some(next) = self.place(i, j) {
+                    reached[next][j + 1] = true;
+                }
+            }
+        }
+        reached
+    }
+}
+
+/// Number of ways to fill in the unknown cells so the runs of filled cells have the given
+/// lengths, in order. Saturates at `usize::MAX`.
+pub fn count_line(cells: &[Cell], runs: &[usize]) -> Result<usize, EmptyRun> {
+    Ok(LineDp::new(cells, runs)?.ways_to_end()[0][0])
+}
+
+/// The line with every cell that's the same in all arrangements filled in, or `None` if
+/// there aren't any arrangements.
+#[allow(dead_code)]
+pub fn solve_line(cells: &[Cell], runs: &[usize]) -> Result<Option<Vec<Cell>>, EmptyRun> {
+    let dp = LineDp::new(cells, runs)?;
+    let ways = dp.ways_to_end();
+    if ways[0][0] == 0 {
+        return Ok(None);
+    }
+    let reached = dp.reachable();
+    let mut can_be_empty = vec![false; cells.len()];
+    let mut can_be_filled = vec![false; cells.len()];
+    for i in 0..cells.len() {
+        for j in 0..=runs.len() {
+            // Only steps on a path from the start to the end count
+            if !reached[i][j] {
+                continue;
+            }
+            if dp.can_skip(i) && ways[i + 1][j] > 0 {
+                can_be_empty[i] = true;
+            }
+            if dp.place(i, j).is_some_and(|next| ways[next][j + 1] > 0) {
+                let end = i + runs[j];
+                can_be_filled[i..end].fill(true);
+                if end < cells.len() {
+                    can_be_empty[end] = true;
+                }
+            }
+        }
+    }
+    Ok(Some(
+        can_be_empty
+            .into_iter()
+            .zip(can_be_filled)
+            .map(|options| match options {
+                (true, false) => Cell::Empty,
+                (false, true) => Cell::Filled,
+                _ => Cell::Unknown,
+            })
+            .collect(),
+    ))
+}
+
+/// Lengths of the runs of filled cells in a line.
+#[allow(dead_code)]
+pub fn runs_of(cells: &[Cell]) -> Vec<usize> {
+    cells
+        .split(|&cell| cell != Cell::Filled)
+        .map(<[Cell]>::len)
+        .filter(|&len| len > 0)
+        .collect()
+}
+
+/// How many ways a puzzle can be solved, with the solutions found.
+#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Solutions {
+    None,
+    Unique(Array2<Cell>),
+    /// Two of the solutions, there may be more
+    Several(Array2<Cell>, Array2<Cell>),
+}
+
+/// A 2D puzzle with the runs of filled cells given for every row and column.
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
+pub struct Nonogram {
+    rows: Vec<Vec<usize>>,
+    columns: Vec<Vec<usize>>,
+}
+#[allow(dead_code)]
+impl Nonogram {
+    pub fn new(rows: Vec<Vec<usize>>, columns: Vec<Vec<usize>>) -> Result<Self, EmptyRun> {
+        if rows.iter().chain(&columns).any(|runs| runs.contains(&0)) {
+            return Err(EmptyRun);
+        }
+        Ok(Self { rows, columns })
+    }
+    /// The clues that describe a finished picture.
+    pub fn from_picture(picture: &Array2<Cell>) -> Self {
+        let runs = |axis| {
+            picture
+                .axis_iter(axis)
+                .map(|line| runs_of(&line.to_vec()))
+                .collect()
+        };
+        Self {
+            rows: runs(Axis(0)),
+            columns: runs(Axis(1)),
+        }
+    }
+    pub fn empty_grid(&self) -> Array2<Cell> {
+        Array2::from_elem((self.rows.len(), self.columns.len()), Cell::Unknown)
+    }
+    /// Solves one line in place, returning which cells changed, or `None` if it can't be
+    /// solved.
+    fn update_line(mut line: ArrayViewMut1<Cell>, runs: &[usize]) -> Option<Vec<usize>> {
+        // The clues were checked when the puzzle was made
+        let solved = solve_line(&line.to_vec(), runs).ok()??;
+        let mut changed = vec![];
+        for (i, (cell, new)) in line.iter_mut().zip(solved).enumerate() {
+            if *cell != new {
+                *cell = new;
+                changed.push(i);
+            }
+        }
+        Some(changed)
+    }
+    /// Fills in every cell forced by the clues of its row or column, going back over lines
+    /// crossing any changed cell until nothing changes. Returns `false` on a contradiction.
+    pub fn propagate(&self, grid: &mut Array2<Cell>) -> bool {
+        // Rows are 0..height and columns follow on after them
+        let height = self.rows.len();
+        let mut queued = vec![true; height + self.columns.len()];
+        let mut queue: VecDeque<usize> = (0..queued.len()).collect();
+        while let Some(line) = queue.pop_front() {
+            queued[line] = false;
+            let changed = if line < height {
+                Self::update_line(grid.row_mut(line), &self.rows[line])
+            } else {
+                Self::update_line(grid.column_mut(line - height), &self.columns[line - height])
+            };
+            let Some(changed) = changed else {
+                return false;
+            };
+            for crossing in changed {
+                let crossing = if line < height {
+                    height + crossing
+                } else {
+                    crossing
+                };
+                if !queued[crossing] {
+                    queued[crossing] = true;
+                    queue.push_back(crossing);
+                }
+            }
+        }
+        true
+    }
+    /// Propagates, then guesses the first unknown cell both ways, stopping after finding
+    /// two solutions.
+    fn search(&self, mut grid: Array2<Cell>, found: &mut Vec<Array2<Cell>>) {
+        if found.len() >= 2 || !self.propagate(&mut grid) {
+            return;
+        }
+        let Some(guess) = grid
+            .indexed_iter()
+            .find(|(_, &cell)| cell == Cell::Unknown)
+            .map(|(pos, _)| pos)
+        else {
+            found.push(grid);
+            return;
+        };
+        for cell in [Cell::Filled, Cell::Empty] {
+            let mut next = grid.clone();
+            next[guess] = cell;
+            self.search(next, found);
+        }
+    }
+    pub fn solve(&self) -> Solutions {
+        let mut found = vec![];
+        self.search(self.empty_grid(), &mut found);
+        let mut found = found.into_iter();
+        match (found.next(), found.next()) {
+            (None, _) => Solutions::None,
+            (Some(solution), None) => Solutions::Unique(solution),
+            (Some(first), Some(second)) => Solutions::Several(first, second),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::{
+        grid2d::{format_grid, parse_grid},
+        test_rng::XorShift,
+    };
+    use std::collections::HashMap;
+
+    fn line(cells: &str) -> Vec<Cell> {
+        cells
+            .chars()
+            .map(|c| Cell::try_from_char(c).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn lines() {
+        let solved = |cells, runs: &[usize]| solve_line(&line(cells), runs).unwrap();
+        assert_eq!(count_line(&line("???.###"), &[1, 1, 3]).unwrap(), 1);
+        assert_eq!(count_line(&line("?###????????"), &[3, 2, 1]).unwrap(), 10);
+        assert_eq!(count_line(&line("#.#"), &[1]).unwrap(), 0);
+        assert_eq!(count_line(&line("..."), &[]).unwrap(), 1);
+        assert_eq!(solved("?????", &[4]), Some(line("?###?")));
+        assert_eq!(solved("??#??", &[1, 1]), Some(line("?.#.?")));
+        assert_eq!(solved("?#???", &[3]), Some(line("?##?.")));
+        assert_eq!(solved("#?#", &[1]), None);
+        assert_eq!(runs_of(&line(".##.#..###")), [2, 1, 3]);
+        assert_eq!(count_line(&line("?.?"), &[1, 0]), Err(EmptyRun));
+        assert_eq!(solve_line(&line("?.?"), &[0]), Err(EmptyRun));
+    }
+    #[test]
+    fn picture() {
+        let picture: Array2<Cell> = parse_grid(
+            "..##..##..
+.####.###.
+##########
+##########
+.########.
+..######..
+...####...
+....##....
+..........
+#.#.##.#.#",
+        )
+        .unwrap();
+        let puzzle = Nonogram::from_picture(&picture);
+        assert_eq!(puzzle.rows[9], [1, 1, 2, 1, 1]);
+        assert_eq!(puzzle.solve(), Solutions::Unique(picture));
+    }
+    #[test]
+    fn needs_guessing() {
+        // Either diagonal fits the clues
+        let puzzle = Nonogram::new(vec![vec![1], vec![1]], vec![vec![1], vec![1]]).unwrap();
+        let mut grid = puzzle.empty_grid();
+        assert!(puzzle.propagate(&mut grid));
+        assert_eq!(format_grid(&grid), "??\n??\n");
+        let Solutions::Several(a, b) = puzzle.solve() else {
+            panic!("Expected two solutions");
+        };
+        assert_eq!(format_grid(&a), "#.\n.#\n");
+        assert_eq!(format_grid(&b), ".#\n#.\n");
+
+        let impossible = Nonogram::new(vec![vec![2], vec![]], vec![vec![], vec![]]).unwrap();
+        assert_eq!(impossible.solve(), Solutions::None);
+        assert_eq!(
+            Nonogram::new(vec![vec![1], vec![]], vec![vec![1, 0], vec![]]).unwrap_err(),
+            EmptyRun
+        );
+    }
+    type Clues = (Vec<Vec<usize>>, Vec<Vec<usize>>);
+    #[test]
+    fn against_brute_force() {
+        let (height, width) = (4, 4);
+        // How many pictures share each set of clues
+        let mut pictures: HashMap<Clues, usize> = HashMap::new();
+        for bits in 0..1u32 << (height * width) {
+            let grid = Array2::from_shape_fn((height, width), |(y, x)| {
+                if bits & (1 << (y * width + x)) == 0 {
+                    Cell::Empty
+                } else {
+                    Cell::Filled
+                }
+            });
+            let puzzle = Nonogram::from_picture(&grid);
+            *pictures.entry((puzzle.rows, puzzle.columns)).or_default() += 1;
+        }
+        let mut rng = XorShift::new(12);
+        for _ in 0..200 {
+            let picture = Array2::from_shape_fn((height, width), |_| {
+                if rng.range(0..2) == 0 {
+                    Cell::Empty
+                } else {
+                    Cell::Filled
+                }
+            });
+            let puzzle = Nonogram::from_picture(&picture);
+            let matching = pictures[&(puzzle.rows.clone(), puzzle.columns.clone())];
+            match puzzle.solve() {
+                Solutions::None => panic!("The picture itself is a solution"),
+                Solutions::Unique(solution) => {
+                    assert_eq!(matching, 1);
+                    assert_eq!(solution, picture);
+                }
+                Solutions::Several(a, b) => {
+                    assert!(matching > 1);
+                    assert_ne!(a, b);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,121 @@
+/// Returns `(g, x, y)` where `g = gcd(a, b)` and `a * x + b * y = g`.
+pub fn ext_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    if b == 0 {
+        (a.abs(), a.signum(), 0)
+    } else {
+        let (gcd, x, y) = ext_gcd(b, a.rem_euclid(b));
+        (gcd, y, x - a.div_euclid(b) * y)
+    }
+}
+
+/// `a + b mod m` without overflowing, for `0 <= a, b < m`.
+const fn add_mod(a: u128, b: u128, m: u128) -> u128 {
+    if a >= m - b {
+        a - (m - b)
+    } else {
+        a + b
+    }
+}
+
+/// `a * b mod m` without overflowing, for non-negative `a` and `b` and positive `m`.
+#[allow(clippy::cast_sign_loss, clippy::cast_possible_wrap)]
+const fn mul_mod(a: i128, b: i128, m: i128) -> i128 {
+    let m = m as u128;
+    let (mut a, mut b) = (a as u128 % m, b as u128 % m);
+    if let Some(product) = a.checked_mul(b) {
+        return (product % m) as i128;
+    }
+    let mut result = 0;
+    while b > 0 {
+        if b & 1 == 1 {
+            result = add_mod(result, a, m);
+        }
+        a = add_mod(a, a, m);
+        b >>= 1;
+    }
+    // Below m, so it fits back in an i128
+    result as i128
+}
+
+/// Solves `x ≡ offset (mod period)` for every `(offset, period)` pair, with moduli that
+/// don't have to be coprime.
+///
+/// Returns the smallest non-negative `x` together with the combined period, or `None`
+/// if the congruences contradict each other (or the combined period overflows).
+#[allow(clippy::cast_sign_loss, clippy::cast_possible_wrap)]
+pub fn crt(congruences: impl IntoIterator<Item = (i128, i128)>) -> Option<(i128, i128)> {
+    let mut solution = 0;
+    let mut modulus = 1;
+    for (offset, period) in congruences {
+        assert!(period > 0, "Period must be positive");
+        let offset = offset.rem_euclid(period);
+        let (gcd, inverse, _) = ext_gcd(modulus, period);
+        let diff = offset - solution;
+        if diff % gcd != 0 {
+            return None;
+        }
+        let reduced = period / gcd;
+        // k such that solution + modulus * k ≡ offset (mod period)
+        let k = mul_mod(
+            (diff / gcd).rem_euclid(reduced),
+            inverse.rem_euclid(reduced),
+            reduced,
+        );
+        let lcm = modulus.checked_mul(reduced)?;
+        let step = mul_mod(modulus, k, lcm);
+        solution = add_mod(solution as u128, step as u128, lcm as u128) as i128;
+        modulus = lcm;
+    }
+    Some((solution, modulus))
+}
+
+/// First step where every cycle is hit, where a cycle is hit at `offset`, `offset + period`,
+/// `offset + 2 * period` and so on.
+pub fn first_common_hit(cycles: impl IntoIterator<Item = (i128, i128)>) -> Option<i128> {
+    let cycles: Vec<(i128, i128)> = cycles.into_iter().collect();
+    let min_step = cycles.iter().map(|&(offset, _)| offset).max().unwrap_or(0);
+    let (x, period) = crt(cycles)?;
+    let behind = (min_step - x).max(0);
+    Some(x + (behind + period - 1) / period * period)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn congruences() {
+        assert_eq!(crt([(2, 3), (3, 5), (2, 7)]), Some((23, 105)));
+        assert_eq!(crt([(1, 4), (3, 6)]), Some((9, 12)));
+        assert_eq!(crt([(1, 4), (2, 6)]), None);
+        assert_eq!(crt([]), Some((0, 1)));
+        let big = 1_000_000_000_000_000_003;
+        assert_eq!(crt([(5, big), (0, 2)]), Some((big + 5, 2 * big)));
+        // Combined periods past i128::MAX / 2 used to overflow when doubling
+        let huge = i128::MAX / 2;
+        assert_eq!(crt([(huge - 1, huge), (1, 2)]), Some((2 * huge - 1, 2 * huge)));
+    }
+    #[test]
+    fn large_moduli() {
+        let m = i128::MAX;
+        assert_eq!(mul_mod(m - 1, m - 1, m), 1);
+        assert_eq!(mul_mod(m - 1, 2, m), m - 2);
+        assert_eq!(mul_mod(m, 5, 7), 5);
+        assert_eq!(mul_mod(m / 2 + 3, m / 2, m / 2 + 1), m / 2 - 1);
+    }
+    #[test]
+    fn brute_force() {
+        for (a, m) in (0..6).flat_map(|m| (0..=m).map(move |a| (a, m + 1))) {
+            for (b, n) in (0..8).flat_map(|n| (0..=n).map(move |b| (b, n + 1))) {
+                let expected = (0..m * n).find(|x| x % m == a % m && x % n == b % n);
+                assert_eq!(crt([(a, m), (b, n)]).map(|(x, _)| x), expected);
+            }
+        }
+    }
+    #[test]
+    fn common_hit() {
+        assert_eq!(first_common_hit([(3, 3), (5, 5)]), Some(15));
+        assert_eq!(first_common_hit([(1, 3), (2, 2)]), Some(4));
+        assert_eq!(first_common_hit([(10, 3), (1, 1)]), Some(10));
+        assert_eq!(first_common_hit([(1, 2), (2, 2)]), None);
+    }
+}
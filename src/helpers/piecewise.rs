@@ -0,0 +1,278 @@
+use std::{iter, ops::Range};
+
+use num_traits::{PrimInt, Signed};
+
+use super::{ranges::MergedRange, RangeIntersect};
+
+/// Values in `range` are moved by `offset`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Segment<T> {
+    pub range: Range<T>,
+    pub offset: T,
+}
+impl<T: PrimInt> Segment<T> {
+    pub const fn new(range: Range<T>, offset: T) -> Self {
+        Self { range, offset }
+    }
+    /// Segment moving `range` so that it starts at `destination`.
+    pub fn to_destination(range: Range<T>, destination: T) -> Self {
+        let offset = destination - range.start;
+        Self { range, offset }
+    }
+    fn image(&self) -> Range<T> {
+        self.range.start + self.offset..self.range.end + self.offset
+    }
+}
+
+/// Integer map made of shifted segments, with every value outside them mapping to itself.
+///
+/// Segments are kept sorted, disjoint, without identity offsets and with equal
+/// neighbours merged, so two maps doing the same thing compare equal.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct PiecewiseMap<T> {
+    segments: Vec<Segment<T>>,
+}
+impl<T: PrimInt + Signed> PiecewiseMap<T> {
+    pub const fn identity() -> Self {
+        Self { segments: vec![] }
+    }
+    /// Builds a map where a value covered by several segments uses the first one.
+    pub fn from_segments(segments: impl IntoIterator<Item = Segment<T>>) -> Self {
+        let mut covered = MergedRange::new();
+        let mut resolved = Vec::new();
+        for segment in segments {
+            let new: MergedRange<T> = iter::once(segment.range.clone()).collect();
+            for range in &new.difference(&covered) {
+                resolved.push(Segment::new(range.clone(), segment.offset));
+            }
+            covered.add(segment.range);
+        }
+        resolved.sort_unstable_by_key(|s| s.range.start);
+        Self::normalised(resolved)
+    }
+    fn normalised(sorted: impl IntoIterator<Item = Segment<T>>) -> Self {
+        let mut segments: Vec<Segment<T>> = Vec::new();
+        for segment in sorted {
+            if segment.offset.is_zero() || segment.range.start >= segment.range.end {
+                continue;
+            }
+            match segments.last_mut() {
+                Some(last)
+                    if (last.range.end, last.offset) == (segment.range.start, segment.offset) =>
+                {
+                    last.range.end = segment.range.end;
+                }
+                _ => segments.push(segment),
+            }
+        }
+        Self { segments }
+    }
+    pub fn segments(&self) -> &[Segment<T>] {
+        &self.segments
+    }
+
+    /// Splits `range` into parts that each move by a single offset, gaps included.
+    fn pieces(&self, range: Range<T>) -> Vec<(Range<T>, T)> {
+        let mut pieces = Vec::new();
+        let mut pos = range.start;
+        let first = self
+            .segments
+            .partition_point(|s| s.range.end <= range.start);
+        for segment in &self.segments[first..] {
+            if pos >= range.end || segment.range.start >= range.end {
+                break;
+            }
+            if pos < segment.range.start {
+                pieces.push((pos..segment.range.start, T::zero()));
+                pos = segment.range.start;
+            }
+            let end = segment.range.end.min(range.end);
+            pieces.push((pos..end, segment.offset));
+            pos = end;
+        }
+        if pos < range.end {
+            pieces.push((pos..range.end, T::zero()));
+        }
+        pieces
+    }
+    fn all_pieces(&self) -> Vec<(Range<T>, T)> {
+        self.pieces(T::min_value()..T::max_value())
+    }
+
+    pub fn apply(&self, value: T) -> T {
+        let i = self.segments.partition_point(|s| s.range.end <= value);
+        match self.segments.get(i) {
+            Some(segment) if segment.range.start <= value => value + segment.offset,
+            _ => value,
+        }
+    }
+    pub fn apply_range(&self, range: Range<T>) -> impl Iterator<Item = Range<T>> {
+        self.pieces(range)
+            .into_iter()
+            .map(|(r, offset)| r.start + offset..r.end + offset)
+    }
+    pub fn apply_set(&self, set: &MergedRange<T>) -> MergedRange<T> {
+        set.iter()
+            .flat_map(|range| self.apply_range(range.clone()))
+            .collect()
+    }
+    /// Every value that maps into `set`.
+    pub fn preimage(&self, set: &MergedRange<T>) -> MergedRange<T> {
+        self.all_pieces()
+            .into_iter()
+            .flat_map(|(range, offset)| {
+                let image = range.start + offset..range.end + offset;
+                set.iter()
+                    .filter_map(move |s| s.intersect(&image))
+                    .map(move |r| r.start - offset..r.end - offset)
+            })
+            .collect()
+    }
+    /// Single map doing `self` followed by `next`.
+    pub fn then(&self, next: &Self) -> Self {
+        let mut segments = Vec::new();
+        for (range, offset) in self.all_pieces() {
+            let image = range.start + offset..range.end + offset;
+            for (next_range, next_offset) in next.pieces(image) {
+                segments.push(Segment::new(
+                    next_range.start - offset..next_range.end - offset,
+                    offset + next_offset,
+                ));
+            }
+        }
+        Self::normalised(segments)
+    }
+    pub fn compose<'a>(maps: impl IntoIterator<Item = &'a Self>) -> Self
+    where
+        T: 'a,
+    {
+        maps.into_iter()
+            .fold(Self::identity(), |composed, map| composed.then(map))
+    }
+    /// The inverse map, if every value has exactly one preimage.
+    pub fn invert(&self) -> Option<Self> {
+        let sources: MergedRange<T> = self.segments.iter().map(|s| s.range.clone()).collect();
+        let images: MergedRange<T> = self.segments.iter().map(Segment::image).collect();
+        let images_len = self
+            .segments
+            .iter()
+            .fold(T::zero(), |sum, s| sum + (s.range.end - s.range.start));
+        if images.covered_len() != images_len || images != sources {
+            return None;
+        }
+        let mut inverted: Vec<Segment<T>> = self
+            .segments
+            .iter()
+            .map(|s| Segment::new(s.image(), -s.offset))
+            .collect();
+        inverted.sort_unstable_by_key(|s| s.range.start);
+        Some(Self::normalised(inverted))
+    }
+
+    /// Values covered by more than one of `segments`.
+    pub fn overlaps(segments: &[Segment<T>]) -> MergedRange<T> {
+        segments
+            .iter()
+            .enumerate()
+            .flat_map(|(i, a)| {
+                segments[i + 1..]
+                    .iter()
+                    .filter_map(|b| a.range.intersect(&b.range))
+            })
+            .collect()
+    }
+    /// Values between the lowest and highest of `segments` not covered by any of them.
+    pub fn gaps(segments: &[Segment<T>]) -> MergedRange<T> {
+        let covered: MergedRange<T> = segments.iter().map(|s| s.range.clone()).collect();
+        let (Some(min), Some(end)) = (covered.min(), covered.end()) else {
+            return MergedRange::new();
+        };
+        iter::once(min..end)
+            .collect::<MergedRange<T>>()
+            .difference(&covered)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::cast_possible_wrap)]
+mod tests {
+    use super::*;
+    use crate::helpers::test_rng::XorShift;
+
+    fn random_map(rng: &mut XorShift) -> (Vec<Segment<i64>>, PiecewiseMap<i64>) {
+        let segments: Vec<Segment<i64>> = (0..rng.range(0..5))
+            .map(|_| {
+                let start = rng.range(0..50) as i64;
+                let len = rng.range(1..20) as i64;
+                Segment::to_destination(start..start + len, rng.range(0..60) as i64)
+            })
+            .collect();
+        let map = PiecewiseMap::from_segments(segments.clone());
+        (segments, map)
+    }
+    fn naive_apply(segments: &[Segment<i64>], value: i64) -> i64 {
+        segments
+            .iter()
+            .find(|s| s.range.contains(&value))
+            .map_or(value, |s| value + s.offset)
+    }
+
+    #[test]
+    fn compose_and_apply() {
+        let mut rng = XorShift::new(5);
+        for _ in 0..200 {
+            let layers: Vec<_> = (0..3).map(|_| random_map(&mut rng)).collect();
+            let composed = PiecewiseMap::compose(layers.iter().map(|(_, map)| map));
+            for value in -5..100 {
+                let expected = layers
+                    .iter()
+                    .fold(value, |v, (segments, _)| naive_apply(segments, v));
+                assert_eq!(composed.apply(value), expected);
+            }
+
+            let set: MergedRange<i64> = [0..10, 30..45].into_iter().collect();
+            let image = composed.apply_set(&set);
+            for value in -5..130 {
+                let hits = set
+                    .iter()
+                    .flat_map(Clone::clone)
+                    .any(|v| composed.apply(v) == value);
+                assert_eq!(image.contains(&value), hits);
+            }
+            let preimage = composed.preimage(&image);
+            assert!(set.difference(&preimage).is_empty());
+        }
+    }
+    #[test]
+    fn invert() {
+        // Swaps 0..10 with 10..20
+        let map = PiecewiseMap::from_segments([
+            Segment::to_destination(0..10, 10),
+            Segment::to_destination(10..20, 0),
+        ]);
+        let inverse = map.invert().unwrap();
+        assert_eq!(inverse, map);
+        assert_eq!(map.then(&inverse), PiecewiseMap::identity());
+
+        let not_injective = PiecewiseMap::from_segments([Segment::to_destination(0..10, 5)]);
+        assert_eq!(not_injective.invert(), None);
+    }
+    #[test]
+    fn gaps_and_overlaps() {
+        let segments = [
+            Segment::new(0..10, 1),
+            Segment::new(5..15, 2),
+            Segment::new(20..30, 3),
+        ];
+        assert_eq!(
+            PiecewiseMap::overlaps(&segments).iter().collect::<Vec<_>>(),
+            vec![&(5..10)]
+        );
+        assert_eq!(
+            PiecewiseMap::gaps(&segments).iter().collect::<Vec<_>>(),
+            vec![&(15..20)]
+        );
+        let map = PiecewiseMap::from_segments(segments);
+        assert_eq!((map.apply(7), map.apply(12)), (8, 14));
+    }
+}
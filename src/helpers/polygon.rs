@@ -0,0 +1,158 @@
+#![allow(dead_code)]
+use itertools::Itertools;
+use num_integer::Integer;
+
+use super::grid2d::Position2D;
+
+type Pos = Position2D<isize>;
+
+/// Direction a polygon's vertices go around it, as seen on screen with y pointing down.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Winding {
+    Clockwise,
+    CounterClockwise,
+}
+
+/// Where a point is relative to a polygon.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Containment {
+    Inside,
+    Boundary,
+    Outside,
+}
+
+/// Every edge of the polygon, including the one closing it from the last vertex to the first.
+fn edges(vertices: &[Pos]) -> impl Iterator<Item = (&Pos, &Pos)> {
+    vertices.iter().circular_tuple_windows()
+}
+
+/// Twice the signed area, positive when the vertices go clockwise on screen.
+///
+/// The vertex list doesn't need to repeat the first vertex at the end, but may.
+pub fn signed_double_area(vertices: &[Pos]) -> isize {
+    edges(vertices).map(|(a, b)| a.x * b.y - a.y * b.x).sum()
+}
+
+/// Twice the area, which is always a whole number for lattice polygons.
+pub fn double_area(vertices: &[Pos]) -> isize {
+    signed_double_area(vertices).abs()
+}
+
+pub fn winding(vertices: &[Pos]) -> Option<Winding> {
+    match signed_double_area(vertices).signum() {
+        1 => Some(Winding::Clockwise),
+        -1 => Some(Winding::CounterClockwise),
+        _ => None,
+    }
+}
+
+#[allow(clippy::cast_precision_loss)]
+pub fn perimeter(vertices: &[Pos]) -> f64 {
+    edges(vertices)
+        .map(|(a, b)| ((b.x - a.x) as f64).hypot((b.y - a.y) as f64))
+        .sum()
+}
+
+/// Number of lattice points on the polygon's edges.
+pub fn boundary_points(vertices: &[Pos]) -> isize {
+    edges(vertices)
+        .map(|(a, b)| (b.x - a.x).abs().gcd(&(b.y - a.y).abs()))
+        .sum()
+}
+
+/// Number of lattice points strictly inside the polygon, using Pick's theorem.
+pub fn interior_points(vertices: &[Pos]) -> isize {
+    // A = I + B / 2 - 1
+    (double_area(vertices) - boundary_points(vertices)) / 2 + 1
+}
+
+/// Number of lattice points inside or on the polygon.
+pub fn lattice_points(vertices: &[Pos]) -> isize {
+    interior_points(vertices) + boundary_points(vertices)
+}
+
+pub fn contains(vertices: &[Pos], point: Pos) -> Containment {
+    let mut inside = false;
+    for (a, b) in edges(vertices) {
+        let cross = (b.x - a.x) * (point.y - a.y) - (b.y - a.y) * (point.x - a.x);
+        let within = a.x.min(b.x) <= point.x
+            && point.x <= a.x.max(b.x)
+            && a.y.min(b.y) <= point.y
+            && point.y <= a.y.max(b.y);
+        if cross == 0 && within {
+            return Containment::Boundary;
+        }
+        // Count the edges crossing a ray going east from the point
+        if (a.y > point.y) != (b.y > point.y) {
+            let dy = b.y - a.y;
+            let lhs = (point.x - a.x) * dy;
+            let rhs = (point.y - a.y) * (b.x - a.x);
+            if (dy > 0 && lhs < rhs) || (dy < 0 && lhs > rhs) {
+                inside = !inside;
+            }
+        }
+    }
+    if inside {
+        Containment::Inside
+    } else {
+        Containment::Outside
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn polygon(points: &[(isize, isize)]) -> Vec<Pos> {
+        points.iter().copied().map(Pos::new_xy).collect()
+    }
+
+    #[test]
+    fn square() {
+        let square = polygon(&[(0, 0), (4, 0), (4, 4), (0, 4)]);
+        assert_eq!(double_area(&square), 32);
+        assert_eq!(winding(&square), Some(Winding::Clockwise));
+        assert!((perimeter(&square) - 16.0).abs() < f64::EPSILON);
+        assert_eq!(boundary_points(&square), 16);
+        assert_eq!(interior_points(&square), 9);
+        assert_eq!(lattice_points(&square), 25);
+
+        let reversed: Vec<Pos> = square.iter().rev().copied().collect();
+        assert_eq!(signed_double_area(&reversed), -32);
+        assert_eq!(winding(&reversed), Some(Winding::CounterClockwise));
+        assert_eq!(winding(&polygon(&[(0, 0), (2, 2), (4, 4)])), None);
+    }
+    #[test]
+    fn triangle() {
+        let triangle = polygon(&[(0, 0), (6, 0), (0, 3)]);
+        assert_eq!(double_area(&triangle), 18);
+        assert_eq!(boundary_points(&triangle), 12);
+        assert_eq!(interior_points(&triangle), 4);
+        let naive = (0..=6)
+            .cartesian_product(0..=3)
+            .map(|xy| contains(&triangle, Pos::new_xy(xy)))
+            .counts();
+        assert_eq!(naive[&Containment::Inside], 4);
+        assert_eq!(naive[&Containment::Boundary], 12);
+    }
+    #[test]
+    fn contains_concave() {
+        // U shape opening upwards
+        let shape = polygon(&[
+            (0, 0),
+            (2, 0),
+            (2, 4),
+            (4, 4),
+            (4, 0),
+            (6, 0),
+            (6, 6),
+            (0, 6),
+        ]);
+        assert_eq!(contains(&shape, Pos::new(1, 1)), Containment::Inside);
+        assert_eq!(contains(&shape, Pos::new(3, 2)), Containment::Outside);
+        assert_eq!(contains(&shape, Pos::new(3, 5)), Containment::Inside);
+        assert_eq!(contains(&shape, Pos::new(2, 2)), Containment::Boundary);
+        assert_eq!(contains(&shape, Pos::new(7, 4)), Containment::Outside);
+        assert_eq!(contains(&shape, Pos::new(-1, 0)), Containment::Outside);
+    }
+}
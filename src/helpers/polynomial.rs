@@ -0,0 +1,303 @@
+#![allow(dead_code)]
+use std::{
+    fmt,
+    ops::{Add, Div, Mul, Neg, Sub},
+};
+
+use num_integer::Integer;
+
+/// Exact fraction, always reduced and with a positive denominator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rational {
+    numer: i128,
+    denom: i128,
+}
+impl Rational {
+    pub const ZERO: Self = Self::from_integer(0);
+    pub const ONE: Self = Self::from_integer(1);
+
+    pub fn new(numer: i128, denom: i128) -> Self {
+        assert!(denom != 0, "Denominator can't be zero");
+        let gcd = numer.gcd(&denom) * denom.signum();
+        Self {
+            numer: numer / gcd,
+            denom: denom / gcd,
+        }
+    }
+    pub const fn from_integer(value: i128) -> Self {
+        Self {
+            numer: value,
+            denom: 1,
+        }
+    }
+    pub const fn numer(&self) -> i128 {
+        self.numer
+    }
+    pub const fn denom(&self) -> i128 {
+        self.denom
+    }
+    pub const fn to_integer(self) -> Option<i128> {
+        if self.denom == 1 {
+            Some(self.numer)
+        } else {
+            None
+        }
+    }
+    pub const fn is_zero(&self) -> bool {
+        self.numer == 0
+    }
+}
+impl From<i128> for Rational {
+    fn from(value: i128) -> Self {
+        Self::from_integer(value)
+    }
+}
+impl Add for Rational {
+    type Output = Self;
+    fn add(self, other: Self) -> Self {
+        let lcm = self.denom.lcm(&other.denom);
+        Self::new(
+            self.numer * (lcm / self.denom) + other.numer * (lcm / other.denom),
+            lcm,
+        )
+    }
+}
+impl Neg for Rational {
+    type Output = Self;
+    fn neg(self) -> Self {
+        Self {
+            numer: -self.numer,
+            denom: self.denom,
+        }
+    }
+}
+impl Sub for Rational {
+    type Output = Self;
+    fn sub(self, other: Self) -> Self {
+        self + -other
+    }
+}
+impl Mul for Rational {
+    type Output = Self;
+    fn mul(self, other: Self) -> Self {
+        // Cross-reduce first to keep the products small
+        let a = self.numer.gcd(&other.denom).max(1);
+        let b = other.numer.gcd(&self.denom).max(1);
+        Self::new(
+            (self.numer / a) * (other.numer / b),
+            (self.denom / b) * (other.denom / a),
+        )
+    }
+}
+impl Div for Rational {
+    type Output = Self;
+    fn div(self, other: Self) -> Self {
+        assert!(!other.is_zero(), "Division by zero");
+        self * Self::new(other.denom, other.numer)
+    }
+}
+impl fmt::Display for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.denom == 1 {
+            write!(f, "{}", self.numer)
+        } else {
+            write!(f, "{}/{}", self.numer, self.denom)
+        }
+    }
+}
+
+/// Polynomial with rational coefficients, lowest power first and without trailing zeros.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Polynomial {
+    coefficients: Vec<Rational>,
+}
+impl Polynomial {
+    pub fn new(mut coefficients: Vec<Rational>) -> Self {
+        while coefficients.last().is_some_and(Rational::is_zero) {
+            coefficients.pop();
+        }
+        Self { coefficients }
+    }
+    /// The lowest degree polynomial going through every `(x, y)` point, using Lagrange's
+    /// formula. The `x` values have to be distinct.
+    pub fn interpolate(points: &[(i128, i128)]) -> Self {
+        let mut coefficients = vec![Rational::ZERO; points.len()];
+        for (i, &(x_i, y_i)) in points.iter().enumerate() {
+            // Product of (x - x_j) / (x_i - x_j) over every other point
+            let mut basis = vec![Rational::ONE];
+            for (j, &(x_j, _)) in points.iter().enumerate() {
+                if i == j {
+                    continue;
+                }
+                assert!(x_i != x_j, "Points need distinct x values");
+                let scale = Rational::new(1, x_i - x_j);
+                let mut next = vec![Rational::ZERO; basis.len() + 1];
+                for (power, &c) in basis.iter().enumerate() {
+                    next[power + 1] = next[power + 1] + c * scale;
+                    next[power] = next[power] - c * scale * Rational::from(x_j);
+                }
+                basis = next;
+            }
+            for (total, c) in coefficients.iter_mut().zip(basis) {
+                *total = *total + c * Rational::from(y_i);
+            }
+        }
+        Self::new(coefficients)
+    }
+    pub fn coefficients(&self) -> &[Rational] {
+        &self.coefficients
+    }
+    /// Highest power with a non-zero coefficient, or `None` for the zero polynomial.
+    pub const fn degree(&self) -> Option<usize> {
+        self.coefficients.len().checked_sub(1)
+    }
+    pub fn eval(&self, x: Rational) -> Rational {
+        self.coefficients
+            .iter()
+            .rev()
+            .fold(Rational::ZERO, |total, &c| total * x + c)
+    }
+    pub fn eval_integer(&self, x: i128) -> Option<i128> {
+        self.eval(Rational::from(x)).to_integer()
+    }
+}
+
+/// Integer sequence sampled at `0, 1, 2..`, stored as Newton forward differences so it can be
+/// extended exactly in either direction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForwardDifferences {
+    /// The first value of every row of differences, without trailing zeros
+    leading: Vec<i128>,
+    samples: usize,
+}
+impl ForwardDifferences {
+    pub fn from_samples(samples: &[i128]) -> Self {
+        let mut row = samples.to_vec();
+        let mut leading = vec![];
+        while let Some(&first) = row.first() {
+            leading.push(first);
+            row = row.windows(2).map(|pair| pair[1] - pair[0]).collect();
+        }
+        while leading.last() == Some(&0) {
+            leading.pop();
+        }
+        Self {
+            leading,
+            samples: samples.len(),
+        }
+    }
+    /// Degree of the lowest degree polynomial through the samples, or `None` if they're all 0.
+    pub const fn degree(&self) -> Option<usize> {
+        self.leading.len().checked_sub(1)
+    }
+    /// Whether there were more samples than needed to find the degree, so the last ones
+    /// confirm it.
+    pub const fn is_confirmed(&self) -> bool {
+        self.leading.len() < self.samples
+    }
+    /// Value at any index, before the samples or long after them.
+    pub fn at(&self, index: i128) -> i128 {
+        // Sum of leading[k] * (index choose k), which stays whole for negative indices too
+        let mut binomial = 1;
+        let mut total = 0;
+        for (k, &difference) in (0..).zip(&self.leading) {
+            if k > 0 {
+                binomial = binomial * (index - k + 1) / k;
+            }
+            total += difference * binomial;
+        }
+        total
+    }
+    pub fn to_polynomial(&self) -> Polynomial {
+        let points: Vec<(i128, i128)> = (0..)
+            .take(self.leading.len())
+            .map(|x| (x, self.at(x)))
+            .collect();
+        Polynomial::interpolate(&points)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rationals() {
+        let half = Rational::new(2, 4);
+        assert_eq!((half.numer(), half.denom()), (1, 2));
+        assert_eq!(Rational::new(3, -6), -half);
+        assert_eq!(half + Rational::new(1, 3), Rational::new(5, 6));
+        assert_eq!(half * Rational::from(4), Rational::from(2));
+        assert_eq!(half / Rational::new(1, 4), Rational::from(2));
+        assert_eq!(Rational::new(-3, 9).to_string(), "-1/3");
+        assert_eq!(half.to_integer(), None);
+    }
+    #[test]
+    fn differences() {
+        let triangular = ForwardDifferences::from_samples(&[0, 1, 3, 6, 10]);
+        assert_eq!(triangular.degree(), Some(2));
+        assert!(triangular.is_confirmed());
+        assert_eq!(triangular.at(100), 5050);
+        assert_eq!(triangular.at(-3), 3);
+        assert_eq!(
+            triangular.to_polynomial().coefficients(),
+            [Rational::ZERO, Rational::new(1, 2), Rational::new(1, 2)]
+        );
+
+        let unconfirmed = ForwardDifferences::from_samples(&[1, 2]);
+        assert_eq!(unconfirmed.degree(), Some(1));
+        assert!(!unconfirmed.is_confirmed());
+        assert_eq!(ForwardDifferences::from_samples(&[0, 0, 0]).degree(), None);
+    }
+    #[test]
+    fn interpolate() {
+        // y = x^3 / 6 - 2x + 7 at uneven points
+        let f = |x: i128| Rational::new(x * x * x, 6) - Rational::from(2 * x) + Rational::from(7);
+        let points: Vec<(i128, i128)> = [-6, 0, 6, 18]
+            .into_iter()
+            .map(|x| (x, f(x).to_integer().unwrap()))
+            .collect();
+        let polynomial = Polynomial::interpolate(&points);
+        assert_eq!(polynomial.degree(), Some(3));
+        for x in -10..10 {
+            assert_eq!(polynomial.eval(Rational::from(x)), f(x));
+        }
+        assert_eq!(polynomial.eval_integer(1), None);
+    }
+    #[test]
+    fn beyond_floats() {
+        // Half-integer coefficients get rounded the wrong way when fitted with floats
+        let samples = [3_867, 26_569, 64_360];
+        let quadratic = ForwardDifferences::from_samples(&samples);
+        let polynomial = quadratic.to_polynomial();
+        assert_eq!(
+            polynomial.coefficients(),
+            [
+                Rational::from(3_867),
+                Rational::new(30_315, 2),
+                Rational::new(15_089, 2)
+            ]
+        );
+        let x = 202_300;
+        let rounded = 3_867 + 15_158 * x + 7_545 * x * x;
+        assert_eq!(rounded, 308_784_379_517_267);
+        assert_eq!(quadratic.at(x), 308_763_916_771_117);
+        assert_ne!(quadratic.at(x), rounded);
+
+        // Indices past 2^53, where f64 can't even hold every step count
+        let x: i128 = (1 << 55) + 1;
+        let exact = (2 * 3_867 + 30_315 * x + 15_089 * x * x) / 2;
+        assert_eq!(quadratic.at(x), exact);
+        assert_eq!(polynomial.eval_integer(x), Some(exact));
+        assert_ne!((x as f64) as i128, x);
+        let float = 7_544.5f64.mul_add(x as f64, 15_157.5).mul_add(x as f64, 3_867.0);
+        assert_ne!(float as i128, exact);
+
+        // Values too large for f64 to hold exactly
+        let big = 1_000_000_000_000_000_003;
+        let line = ForwardDifferences::from_samples(&[big, big + 3, big + 6]);
+        assert_eq!(line.at(1_000), big + 3_000);
+        assert_ne!((big as f64) as i128, big);
+    }
+}
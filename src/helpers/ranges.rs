@@ -0,0 +1,293 @@
+use std::{
+    iter,
+    ops::{Add, Range, RangeBounds, RangeInclusive, Sub},
+    slice,
+};
+
+use itertools::Itertools;
+use num_traits::{One, Zero};
+
+pub trait RangeIntersect<T: Ord, U: RangeBounds<T>>: RangeBounds<T> {
+    /// The overlapping part of both ranges, or `None` if they don't share any value.
+    fn intersect(&self, other: &U) -> Option<U>;
+}
+impl<T: Ord + Copy> RangeIntersect<T, Self> for Range<T> {
+    fn intersect(&self, other: &Self) -> Option<Self> {
+        let start = self.start.max(other.start);
+        let end = self.end.min(other.end);
+        (start < end).then_some(start..end)
+    }
+}
+impl<T: Ord + Copy> RangeIntersect<T, Self> for RangeInclusive<T> {
+    fn intersect(&self, other: &Self) -> Option<Self> {
+        let start = *self.start().max(other.start());
+        let end = *self.end().min(other.end());
+        (start <= end).then_some(start..=end)
+    }
+}
+
+/// Set of values stored as sorted, disjoint and non-touching half-open ranges.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MergedRange<T> {
+    ranges: Vec<Range<T>>,
+}
+impl<T: Ord + Copy> MergedRange<T> {
+    pub const fn new() -> Self {
+        Self { ranges: vec![] }
+    }
+    /// Builds from ranges that are already sorted by start, merging as it goes.
+    fn from_sorted(sorted: impl IntoIterator<Item = Range<T>>) -> Self {
+        let mut ranges: Vec<Range<T>> = Vec::new();
+        for range in sorted.into_iter().filter(|r| r.start < r.end) {
+            match ranges.last_mut() {
+                Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+                _ => ranges.push(range),
+            }
+        }
+        Self { ranges }
+    }
+    pub fn add(&mut self, other: Range<T>) {
+        if other.start >= other.end {
+            return;
+        }
+        // Ranges in lo..hi overlap or touch the new one
+        let lo = self.ranges.partition_point(|r| r.end < other.start);
+        let hi = self.ranges.partition_point(|r| r.start <= other.end);
+        let mut merged = other;
+        if lo < hi {
+            merged.start = merged.start.min(self.ranges[lo].start);
+            merged.end = merged.end.max(self.ranges[hi - 1].end);
+        }
+        self.ranges.splice(lo..hi, iter::once(merged));
+    }
+    pub fn remove(&mut self, other: Range<T>) {
+        if other.start >= other.end {
+            return;
+        }
+        // Ranges in lo..hi overlap the removed one
+        let lo = self.ranges.partition_point(|r| r.end <= other.start);
+        let hi = self.ranges.partition_point(|r| r.start < other.end);
+        if lo >= hi {
+            return;
+        }
+        let left = self.ranges[lo].start..other.start;
+        let right = other.end..self.ranges[hi - 1].end;
+        self.ranges.splice(
+            lo..hi,
+            [left, right].into_iter().filter(|r| r.start < r.end),
+        );
+    }
+    pub fn contains(&self, value: &T) -> bool {
+        let i = self.ranges.partition_point(|r| r.end <= *value);
+        self.ranges.get(i).is_some_and(|r| r.start <= *value)
+    }
+    pub fn contains_range(&self, range: &Range<T>) -> bool {
+        if range.start >= range.end {
+            return true;
+        }
+        let i = self.ranges.partition_point(|r| r.end <= range.start);
+        self.ranges
+            .get(i)
+            .is_some_and(|r| r.start <= range.start && range.end <= r.end)
+    }
+    pub fn union(&self, other: &Self) -> Self {
+        Self::from_sorted(
+            self.ranges
+                .iter()
+                .merge_by(&other.ranges, |a, b| a.start <= b.start)
+                .cloned(),
+        )
+    }
+    pub fn intersection(&self, other: &Self) -> Self {
+        let mut ranges = Vec::new();
+        let (mut a, mut b) = (
+            self.ranges.iter().peekable(),
+            other.ranges.iter().peekable(),
+        );
+        while let (Some(ra), Some(rb)) = (a.peek(), b.peek()) {
+            if let Some(intersect) = ra.intersect(rb) {
+                ranges.push(intersect);
+            }
+            if ra.end < rb.end {
+                a.next();
+            } else {
+                b.next();
+            }
+        }
+        Self { ranges }
+    }
+    pub fn difference(&self, other: &Self) -> Self {
+        let mut new = self.clone();
+        for range in &other.ranges {
+            new.remove(range.clone());
+        }
+        new
+    }
+    pub fn ranges(&self) -> &[Range<T>] {
+        &self.ranges
+    }
+    pub fn iter(&self) -> slice::Iter<'_, Range<T>> {
+        self.ranges.iter()
+    }
+    pub const fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+    pub fn min(&self) -> Option<T> {
+        self.ranges.first().map(|r| r.start)
+    }
+    /// The exclusive upper bound of the set.
+    pub fn end(&self) -> Option<T> {
+        self.ranges.last().map(|r| r.end)
+    }
+}
+impl<T: Ord + Copy + Zero + Sub<Output = T>> MergedRange<T> {
+    /// Total number of values covered by the set.
+    pub fn covered_len(&self) -> T {
+        self.ranges
+            .iter()
+            .fold(T::zero(), |sum, r| sum + (r.end - r.start))
+    }
+}
+impl<T: Ord + Copy + One + Add<Output = T> + Sub<Output = T>> MergedRange<T> {
+    pub fn add_inclusive(&mut self, other: RangeInclusive<T>) {
+        if other.start() <= other.end() {
+            self.add(*other.start()..*other.end() + T::one());
+        }
+    }
+    pub fn remove_inclusive(&mut self, other: RangeInclusive<T>) {
+        if other.start() <= other.end() {
+            self.remove(*other.start()..*other.end() + T::one());
+        }
+    }
+    pub fn iter_inclusive(&self) -> impl Iterator<Item = RangeInclusive<T>> + '_ {
+        self.ranges.iter().map(|r| r.start..=r.end - T::one())
+    }
+}
+impl<T: Ord + Copy> Default for MergedRange<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl<T: Ord + Copy> FromIterator<Range<T>> for MergedRange<T> {
+    fn from_iter<Q>(iter: Q) -> Self
+    where
+        Q: IntoIterator<Item = Range<T>>,
+    {
+        let mut ranges: Vec<Range<T>> = iter.into_iter().collect();
+        ranges.sort_unstable_by_key(|r| r.start);
+        Self::from_sorted(ranges)
+    }
+}
+impl<T: Ord + Copy + One + Add<Output = T>> FromIterator<RangeInclusive<T>> for MergedRange<T> {
+    fn from_iter<Q>(iter: Q) -> Self
+    where
+        Q: IntoIterator<Item = RangeInclusive<T>>,
+    {
+        iter.into_iter()
+            .filter(|r| r.start() <= r.end())
+            .map(|r| *r.start()..*r.end() + T::one())
+            .collect()
+    }
+}
+impl<T: Ord + Copy> Extend<Range<T>> for MergedRange<T> {
+    fn extend<Q: IntoIterator<Item = Range<T>>>(&mut self, iter: Q) {
+        *self = Self::from_sorted(self.ranges.drain(..).merge_by(
+            iter.into_iter().sorted_unstable_by_key(|r| r.start),
+            |a, b| a.start <= b.start,
+        ));
+    }
+}
+impl<'a, T> IntoIterator for &'a MergedRange<T> {
+    type Item = &'a Range<T>;
+    type IntoIter = slice::Iter<'a, Range<T>>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.ranges.iter()
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::cast_possible_truncation)]
+mod tests {
+    use super::*;
+    use crate::helpers::test_rng::XorShift;
+
+    const SIZE: u64 = 100;
+    fn random_range(rng: &mut XorShift) -> Range<u64> {
+        let start = rng.range(0..SIZE);
+        start..rng.range(start..SIZE + 1)
+    }
+    fn bitmap(set: &MergedRange<u64>) -> Vec<bool> {
+        (0..SIZE).map(|v| set.contains(&v)).collect()
+    }
+    fn assert_normalised(set: &MergedRange<u64>) {
+        assert!(set.iter().all(|r| r.start < r.end));
+        assert!(set.iter().tuple_windows().all(|(a, b)| a.end < b.start));
+    }
+
+    #[test]
+    fn intersect() {
+        assert_eq!((0..5).intersect(&(3..8)), Some(3..5));
+        assert_eq!((0..5).intersect(&(5..8)), None);
+        assert_eq!((0..=5).intersect(&(5..=8)), Some(5..=5));
+        assert_eq!((0..=4).intersect(&(5..=8)), None);
+    }
+    #[test]
+    fn inclusive() {
+        let mut set: MergedRange<i32> = [1..=3, 5..=5, 4..=4].into_iter().collect();
+        assert_eq!(set.iter().collect::<Vec<_>>(), vec![&(1..6)]);
+        set.remove_inclusive(3..=3);
+        assert_eq!(set.iter_inclusive().collect::<Vec<_>>(), [1..=2, 4..=5]);
+        assert_eq!(set.covered_len(), 4);
+    }
+    #[test]
+    fn against_bitmap() {
+        let mut rng = XorShift::new(2023);
+        for _ in 0..200 {
+            let mut set = MergedRange::new();
+            let mut naive = vec![false; SIZE as usize];
+            for _ in 0..20 {
+                let range = random_range(&mut rng);
+                let add = !rng.next_u64().is_multiple_of(3);
+                for v in range.clone() {
+                    naive[v as usize] = add;
+                }
+                if add {
+                    set.add(range);
+                } else {
+                    set.remove(range);
+                }
+                assert_normalised(&set);
+                assert_eq!(bitmap(&set), naive);
+                assert_eq!(
+                    set.covered_len(),
+                    naive.iter().filter(|&&b| b).count() as u64
+                );
+            }
+
+            let other: MergedRange<u64> = (0..10).map(|_| random_range(&mut rng)).collect();
+            assert_normalised(&other);
+            let (a, b) = (bitmap(&set), bitmap(&other));
+            let union = set.union(&other);
+            let intersection = set.intersection(&other);
+            let difference = set.difference(&other);
+            let mut extended = set.clone();
+            extended.extend(other.iter().cloned());
+            for (set, op) in [
+                (&union, (|a, b| a || b) as fn(bool, bool) -> bool),
+                (&extended, |a, b| a || b),
+                (&intersection, |a, b| a && b),
+                (&difference, |a, b| a && !b),
+            ] {
+                assert_normalised(set);
+                let expected: Vec<bool> = a.iter().zip(&b).map(|(&a, &b)| op(a, b)).collect();
+                assert_eq!(bitmap(set), expected);
+            }
+
+            let probe = random_range(&mut rng);
+            assert_eq!(
+                set.contains_range(&probe),
+                probe.clone().all(|v| a[v as usize])
+            );
+        }
+    }
+}
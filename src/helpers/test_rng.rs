@@ -0,0 +1,18 @@
+use std::ops::Range;
+
+/// Small deterministic xorshift generator for randomised tests.
+pub struct XorShift(u64);
+impl XorShift {
+    pub const fn new(seed: u64) -> Self {
+        Self(seed | 1)
+    }
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+    pub fn range(&mut self, range: Range<u64>) -> u64 {
+        range.start + self.next_u64() % (range.end - range.start)
+    }
+}
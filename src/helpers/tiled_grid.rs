@@ -0,0 +1,109 @@
+#![allow(dead_code)]
+#![allow(clippy::cast_possible_wrap)]
+use std::collections::HashMap;
+
+use ndarray::Array2;
+
+use super::grid2d::Position2D;
+
+type Pos = Position2D<isize>;
+
+/// View of a grid repeated infinitely in every direction.
+///
+/// Positions are signed, with tile `(0, 0)` being the base grid itself.
+#[derive(Debug, Clone, Copy)]
+pub struct TiledGrid<'a, T> {
+    grid: &'a Array2<T>,
+    width: isize,
+    height: isize,
+}
+impl<'a, T> TiledGrid<'a, T> {
+    pub fn new(grid: &'a Array2<T>) -> Self {
+        let [height, width] = *grid.shape() else {
+            panic!()
+        };
+        assert!(height > 0 && width > 0, "Can't tile an empty grid");
+        Self {
+            grid,
+            width: width as isize,
+            height: height as isize,
+        }
+    }
+    pub const fn width(&self) -> isize {
+        self.width
+    }
+    pub const fn height(&self) -> isize {
+        self.height
+    }
+    /// The position inside the base grid that `pos` is a copy of.
+    pub const fn base_pos(&self, pos: Pos) -> Position2D<usize> {
+        Position2D::new(
+            pos.x.rem_euclid(self.width) as usize,
+            pos.y.rem_euclid(self.height) as usize,
+        )
+    }
+    /// Which copy of the grid `pos` falls in.
+    pub const fn tile_of(&self, pos: Pos) -> Pos {
+        Pos::new(pos.x.div_euclid(self.width), pos.y.div_euclid(self.height))
+    }
+    pub const fn split(&self, pos: Pos) -> (Pos, Position2D<usize>) {
+        (self.tile_of(pos), self.base_pos(pos))
+    }
+    pub fn get(&self, pos: Pos) -> &'a T {
+        &self.grid[self.base_pos(pos).yx()]
+    }
+    /// Fold positions into one accumulator per tile.
+    pub fn aggregate_per_tile<'b, A, I, F>(
+        &self,
+        positions: I,
+        init: A,
+        mut fold: F,
+    ) -> HashMap<Pos, A>
+    where
+        A: Clone,
+        I: IntoIterator<Item = &'b Pos>,
+        F: FnMut(&mut A, Position2D<usize>, &T),
+    {
+        let mut tiles: HashMap<Pos, A> = HashMap::new();
+        for &pos in positions {
+            let (tile, base) = self.split(pos);
+            let acc = tiles.entry(tile).or_insert_with(|| init.clone());
+            fold(acc, base, &self.grid[base.yx()]);
+        }
+        tiles
+    }
+    pub fn count_per_tile<'b, I>(&self, positions: I) -> HashMap<Pos, usize>
+    where
+        I: IntoIterator<Item = &'b Pos>,
+    {
+        self.aggregate_per_tile(positions, 0, |count, _, _| *count += 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn tiles() {
+        let grid = Array2::from_shape_vec((2, 3), vec![0, 1, 2, 3, 4, 5]).unwrap();
+        let tiled = TiledGrid::new(&grid);
+        assert_eq!(*tiled.get(Pos::new(-1, -1)), 5);
+        assert_eq!(*tiled.get(Pos::new(4, 2)), 1);
+        assert_eq!(
+            tiled.split(Pos::new(-4, 5)),
+            (Pos::new(-2, 2), Position2D::new(2, 1))
+        );
+        let positions = [
+            Pos::new(0, 0),
+            Pos::new(2, 1),
+            Pos::new(3, 0),
+            Pos::new(-1, 0),
+        ];
+        let counts = tiled.count_per_tile(&positions);
+        assert_eq!(counts[&Pos::new(0, 0)], 2);
+        assert_eq!(counts[&Pos::new(1, 0)], 1);
+        assert_eq!(counts[&Pos::new(-1, 0)], 1);
+        let sums = tiled.aggregate_per_tile(&positions, 0, |sum, _, v| *sum += v);
+        assert_eq!(sums[&Pos::new(0, 0)], 5);
+    }
+}
@@ -0,0 +1,199 @@
+#![allow(dead_code)]
+use std::collections::VecDeque;
+
+const ROOT: u32 = 0;
+
+/// A dictionary word found in some text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Token<V> {
+    /// Byte offset of the first byte of the word
+    pub start: usize,
+    pub len: usize,
+    pub value: V,
+}
+
+/// Aho–Corasick automaton over bytes, following a transition for every byte so each step is
+/// a single lookup.
+#[derive(Debug, Clone)]
+struct Automaton<V> {
+    transitions: Vec<[u32; 256]>,
+    /// Longest word ending at each state, with its length
+    longest: Vec<Option<(usize, V)>>,
+    max_len: usize,
+}
+impl<V: Copy> Automaton<V> {
+    fn new(words: &[(Vec<u8>, V)]) -> Self {
+        // Trie first, with 0 standing for a missing edge since nothing leads back to the root
+        let mut transitions = vec![[ROOT; 256]];
+        let mut longest = vec![None];
+        for (word, value) in words {
+            let mut state = ROOT;
+            for &byte in word {
+                if transitions[state as usize][byte as usize] == ROOT {
+                    transitions[state as usize][byte as usize] =
+                        u32::try_from(transitions.len()).unwrap();
+                    transitions.push([ROOT; 256]);
+                    longest.push(None);
+                }
+                state = transitions[state as usize][byte as usize];
+            }
+            longest[state as usize] = Some((word.len(), *value));
+        }
+
+        // Then fill in the missing edges breadth first from the failure links, which only
+        // point to shallower states
+        let mut fail = vec![ROOT; transitions.len()];
+        let mut queue: VecDeque<u32> = transitions[ROOT as usize]
+            .iter()
+            .copied()
+            .filter(|&child| child != ROOT)
+            .collect();
+        while let Some(state) = queue.pop_front() {
+            let link = fail[state as usize] as usize;
+            if longest[state as usize].is_none() {
+                longest[state as usize] = longest[link];
+            }
+            let link_row = transitions[link];
+            for (child, linked) in transitions[state as usize].iter_mut().zip(link_row) {
+                if *child == ROOT {
+                    *child = linked;
+                } else {
+                    fail[*child as usize] = linked;
+                    queue.push_back(*child);
+                }
+            }
+        }
+        Self {
+            transitions,
+            longest,
+            max_len: words.iter().map(|(word, _)| word.len()).max().unwrap_or(0),
+        }
+    }
+
+    /// The word starting earliest in `bytes`, taking the longest one if several start there.
+    /// Returns its offset from the start of `bytes`.
+    fn leftmost(&self, bytes: impl Iterator<Item = u8>) -> Option<Token<V>> {
+        let mut state = ROOT;
+        let mut best: Option<Token<V>> = None;
+        for (end, byte) in (1..).zip(bytes) {
+            if best.is_some_and(|token| end > token.start + self.max_len) {
+                // Anything found from here on starts later
+                break;
+            }
+            state = self.transitions[state as usize][byte as usize];
+            if let Some((len, value)) = self.longest[state as usize] {
+                let start = end - len;
+                // A word starting at the same place but ending later is longer
+                if best.is_none_or(|token| start <= token.start) {
+                    best = Some(Token { start, len, value });
+                }
+            }
+        }
+        best
+    }
+}
+
+/// Finds words from a fixed dictionary in text, allowing them to overlap, so `"eightwo"`
+/// starts with `eight` and ends with `two`.
+#[derive(Debug, Clone)]
+pub struct TokenScanner<V> {
+    forward: Automaton<V>,
+    /// Same words spelled backwards, to search from the end of the text
+    backward: Automaton<V>,
+}
+impl<V: Copy> TokenScanner<V> {
+    /// Builds a scanner for the `(word, value)` pairs. A word given twice keeps its last value.
+    pub fn new<'a>(words: impl IntoIterator<Item = (&'a str, V)>) -> Self {
+        let words: Vec<(Vec<u8>, V)> = words
+            .into_iter()
+            .inspect(|(word, _)| assert!(!word.is_empty(), "Words can't be empty"))
+            .map(|(word, value)| (word.as_bytes().to_vec(), value))
+            .collect();
+        let reversed: Vec<(Vec<u8>, V)> = words
+            .iter()
+            .map(|(word, value)| (word.iter().rev().copied().collect(), *value))
+            .collect();
+        Self {
+            forward: Automaton::new(&words),
+            backward: Automaton::new(&reversed),
+        }
+    }
+    /// The word starting earliest, or the longest one starting there.
+    pub fn first(&self, text: &str) -> Option<Token<V>> {
+        self.forward.leftmost(text.bytes())
+    }
+    /// The word ending latest, or the longest one ending there.
+    pub fn last(&self, text: &str) -> Option<Token<V>> {
+        self.backward
+            .leftmost(text.bytes().rev())
+            .map(|token| Token {
+                start: text.len() - token.start - token.len,
+                ..token
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIGITS: [(&str, usize); 10] = [
+        ("zero", 0),
+        ("one", 1),
+        ("two", 2),
+        ("three", 3),
+        ("four", 4),
+        ("five", 5),
+        ("six", 6),
+        ("seven", 7),
+        ("eight", 8),
+        ("nine", 9),
+    ];
+
+    #[test]
+    fn overlapping() {
+        let scanner = TokenScanner::new(DIGITS);
+        let first = scanner.first("xeightwox").unwrap();
+        let last = scanner.last("xeightwox").unwrap();
+        assert_eq!(
+            first,
+            Token {
+                start: 1,
+                len: 5,
+                value: 8
+            }
+        );
+        assert_eq!(
+            last,
+            Token {
+                start: 5,
+                len: 3,
+                value: 2
+            }
+        );
+        // Needs the failure link from "on" to "n" to find "nine"
+        assert_eq!(scanner.first("onine").map(|t| t.value), Some(9));
+        assert_eq!(scanner.last("twone").map(|t| t.value), Some(1));
+        assert_eq!(scanner.first("seve"), None);
+        assert_eq!(scanner.last(""), None);
+    }
+    #[test]
+    fn longest_at_same_place() {
+        let scanner = TokenScanner::new([("seven", 7), ("seventeen", 17), ("teen", 0), ("x", 1)]);
+        assert_eq!(scanner.first("aseventeen").map(|t| t.value), Some(17));
+        assert_eq!(scanner.last("seventeena").map(|t| t.value), Some(17));
+        assert_eq!(scanner.last("seventeenx").map(|t| t.value), Some(1));
+        // "teen" is inside the longer word, but that starts earlier
+        assert_eq!(scanner.first("xseventeen").map(|t| t.start), Some(0));
+        assert_eq!(scanner.first("yseventeen").map(|t| t.start), Some(1));
+    }
+    #[test]
+    fn other_dictionaries() {
+        let scanner = TokenScanner::new([("ten", 10), ("eleven", 11), ("zehn", 10), ("elf", 11)]);
+        assert_eq!(scanner.first("elevelften").map(|t| t.value), Some(11));
+        assert_eq!(scanner.last("elevenzehnx").map(|t| t.value), Some(10));
+        let unicode = TokenScanner::new([("zwölf", 12), ("ölf", 0)]);
+        let token = unicode.last("zwölfzwölf").unwrap();
+        assert_eq!((token.start, token.value), (6, 12));
+    }
+}
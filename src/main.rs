@@ -45,7 +45,7 @@ fn run<T: day::Day>(
     let start_time = Instant::now();
     let parsed = T::parse(input);
     let parsed_time = start_time.elapsed();
-    let parsed = parsed.expect(&format!("Error parsing file {file}")[..]);
+    let parsed = parsed.unwrap_or_else(|e| panic!("Error parsing file {file}: {e:?}"));
 
     let mut times = (0..=1).map(|i| {
         println!("- Part {}:", i + 1);